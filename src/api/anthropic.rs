@@ -0,0 +1,280 @@
+use crate::api::error::GenerationError;
+use crate::api::{message_id, LlmProvider, ANTHROPIC_PROVIDER};
+use crate::bindings::ntwk::theater::http_client::{send_http, HttpRequest};
+use crate::bindings::ntwk::theater::runtime::log;
+use crate::messages::{
+    anthropic::{
+        AnthropicContentBlock, AnthropicMessage, AnthropicRequest, AnthropicResponse, AnthropicTool,
+    },
+    openrouter::OpenRouterUsage,
+    AssistantMessage, GenerationParams, Message, ModelInfo, ToolCall,
+};
+use mcp_protocol::types::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+// Anthropic Messages API client implementation
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AnthropicClient {
+    api_key: String,
+    url: String,
+    model_configs: Vec<ModelInfo>,
+}
+
+impl AnthropicClient {
+    pub fn new(api_key: String, model_configs: Vec<ModelInfo>) -> Self {
+        if api_key.is_empty() {
+            log("Warning: Empty Anthropic API key provided");
+        }
+
+        Self {
+            api_key,
+            url: "https://api.anthropic.com/v1".to_string(),
+            model_configs,
+        }
+    }
+
+    fn require_model(&self, model_id: &str) -> Result<&ModelInfo, Box<dyn std::error::Error>> {
+        Ok(self.model_info(model_id).ok_or("Model not found")?)
+    }
+
+    fn build_request(
+        &self,
+        messages: Vec<Message>,
+        model_id: &str,
+        available_tools: Option<Vec<Tool>>,
+        params: &GenerationParams,
+    ) -> Result<HttpRequest, Box<dyn std::error::Error>> {
+        let model_info = self.require_model(model_id)?;
+
+        let tools = if model_info.tools_enabled {
+            available_tools.map(|tools| {
+                tools
+                    .iter()
+                    .map(|tool| AnthropicTool {
+                        name: tool.name.clone(),
+                        description: tool.description.clone(),
+                        input_schema: tool.input_schema.clone(),
+                    })
+                    .collect()
+            })
+        } else {
+            None
+        };
+
+        let request_body = AnthropicRequest {
+            model: model_id.to_string(),
+            max_tokens: params.max_tokens.unwrap_or(model_info.max_tokens),
+            temperature: params.temperature,
+            top_p: params.top_p,
+            stop_sequences: params.stop.clone(),
+            system: system_prompt(&messages),
+            messages: to_anthropic_messages(&messages),
+            tools,
+        };
+
+        log(&format!(
+            "Anthropic request body: {}",
+            serde_json::to_string(&request_body).unwrap_or_default()
+        ));
+
+        Ok(HttpRequest {
+            method: "POST".to_string(),
+            uri: format!("{}/messages", self.url),
+            headers: vec![
+                ("x-api-key".to_string(), self.api_key.clone()),
+                (
+                    "anthropic-version".to_string(),
+                    ANTHROPIC_VERSION.to_string(),
+                ),
+                ("Content-Type".to_string(), "application/json".to_string()),
+            ],
+            body: Some(serde_json::to_vec(&request_body)?),
+        })
+    }
+
+    // Send a request and return the body of a successful response
+    fn send_request(&self, request: &HttpRequest) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        log("Sending Anthropic request...");
+
+        let http_response = send_http(request).map_err(|e| GenerationError::Network {
+            message: e.to_string(),
+        })?;
+
+        log(&format!(
+            "Anthropic response status: {}",
+            http_response.status
+        ));
+
+        if http_response.status < 200 || http_response.status >= 300 {
+            log(&format!("Anthropic response: {:?}", http_response));
+            return Err(
+                GenerationError::from_http_response(ANTHROPIC_PROVIDER, &http_response).into(),
+            );
+        }
+
+        Ok(http_response
+            .body
+            .ok_or_else(|| GenerationError::InvalidResponse {
+                message: "No response body".to_string(),
+            })?)
+    }
+}
+
+impl LlmProvider for AnthropicClient {
+    fn name(&self) -> &str {
+        ANTHROPIC_PROVIDER
+    }
+
+    fn model_configs(&self) -> &[ModelInfo] {
+        &self.model_configs
+    }
+
+    fn generate_response(
+        &self,
+        messages: Vec<Message>,
+        model_id: String,
+        available_tools: Option<Vec<Tool>>,
+        params: &GenerationParams,
+    ) -> Result<AssistantMessage, Box<dyn std::error::Error>> {
+        let model_info = self.require_model(&model_id)?;
+        let request = self.build_request(messages, &model_id, available_tools, params)?;
+        let body = self.send_request(&request)?;
+
+        let response: AnthropicResponse = serde_json::from_slice(&body)
+            .map_err(|e| GenerationError::from_unparseable_body(ANTHROPIC_PROVIDER, &body, e))?;
+        log(&format!("Parsed Anthropic response: {:?}", response));
+
+        let mut content = String::new();
+        let mut tool_calls = Vec::new();
+        for block in response.content {
+            match block {
+                AnthropicContentBlock::Text { text } => content.push_str(&text),
+                AnthropicContentBlock::ToolUse { id, name, input } => tool_calls.push(ToolCall {
+                    id,
+                    name,
+                    arguments: input.to_string(),
+                }),
+                _ => {}
+            }
+        }
+
+        let stop_reason = response.stop_reason.unwrap_or_default();
+        Ok(AssistantMessage {
+            id: message_id(&content),
+            content,
+            model: response.model,
+            finish_reason: finish_reason(&stop_reason),
+            native_finish_reason: Some(stop_reason),
+            usage: usage(response.usage.input_tokens, response.usage.output_tokens),
+            input_cost_per_million_tokens: model_info.input_cost_per_million_tokens,
+            output_cost_per_million_tokens: model_info.output_cost_per_million_tokens,
+            tool_calls: if tool_calls.is_empty() {
+                None
+            } else {
+                Some(tool_calls)
+            },
+            continuation_of: None,
+            truncated: false,
+            error: None,
+            requested_model: None,
+        })
+    }
+}
+
+// Convert the chain into Anthropic messages. Tool results travel as user content blocks,
+// and consecutive turns from the same role have to be merged into one message.
+fn to_anthropic_messages(messages: &[Message]) -> Vec<AnthropicMessage> {
+    let mut result: Vec<AnthropicMessage> = Vec::new();
+
+    for msg in messages {
+        let (role, blocks) = match msg {
+            // Sent through the top-level system field instead
+            Message::System(_) => continue,
+            Message::User(msg) => (
+                "user",
+                vec![AnthropicContentBlock::Text {
+                    text: msg.content.clone(),
+                }],
+            ),
+            Message::Assistant(msg) => {
+                let mut blocks = Vec::new();
+                if !msg.content.is_empty() {
+                    blocks.push(AnthropicContentBlock::Text {
+                        text: msg.content.clone(),
+                    });
+                }
+                for call in msg.tool_calls.iter().flatten() {
+                    blocks.push(AnthropicContentBlock::ToolUse {
+                        id: call.id.clone(),
+                        name: call.name.clone(),
+                        input: serde_json::from_str::<Value>(&call.arguments)
+                            .unwrap_or_else(|_| json!({})),
+                    });
+                }
+                ("assistant", blocks)
+            }
+            Message::Tool(msg) => (
+                "user",
+                vec![AnthropicContentBlock::ToolResult {
+                    tool_use_id: msg.tool_call_id.clone(),
+                    content: msg.content.clone(),
+                }],
+            ),
+        };
+
+        if blocks.is_empty() {
+            continue;
+        }
+
+        match result.last_mut() {
+            Some(last) if last.role == role => last.content.extend(blocks),
+            _ => result.push(AnthropicMessage {
+                role: role.to_string(),
+                content: blocks,
+            }),
+        }
+    }
+
+    result
+}
+
+// Anthropic takes system instructions as a request field rather than a message role
+fn system_prompt(messages: &[Message]) -> Option<String> {
+    let prompts: Vec<&str> = messages
+        .iter()
+        .filter_map(|msg| match msg {
+            Message::System(msg) => Some(msg.content.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    if prompts.is_empty() {
+        None
+    } else {
+        Some(prompts.join("\n\n"))
+    }
+}
+
+// Map Anthropic stop reasons onto the OpenAI-style finish reasons used by the chain
+fn finish_reason(stop_reason: &str) -> String {
+    match stop_reason {
+        "end_turn" | "stop_sequence" => "stop",
+        "max_tokens" => "length",
+        "tool_use" => "tool_calls",
+        "refusal" => "content_filter",
+        _ => "error",
+    }
+    .to_string()
+}
+
+fn usage(input_tokens: u32, output_tokens: u32) -> OpenRouterUsage {
+    OpenRouterUsage {
+        prompt_tokens: input_tokens,
+        completion_tokens: output_tokens,
+        total_tokens: input_tokens + output_tokens,
+        ..Default::default()
+    }
+}
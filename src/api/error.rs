@@ -0,0 +1,231 @@
+use crate::bindings::ntwk::theater::http_client::HttpResponse;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+/// Everything that can go wrong while producing an assistant turn.
+///
+/// Providers return these boxed as `Box<dyn Error>`, so the generation loop recovers them
+/// with `GenerationError::from_boxed`. The serialized form is stored on failed chain entries
+/// and its `code` is what WebSocket clients receive.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum GenerationError {
+    /// The provider answered with an error status; `details` is its parsed error JSON
+    Provider {
+        provider: String,
+        status: u16,
+        message: String,
+        details: Option<Value>,
+    },
+    RateLimited {
+        provider: String,
+        message: String,
+        retry_after: Option<u64>, // Seconds, from the Retry-After header
+    },
+    Auth {
+        provider: String,
+        message: String,
+    },
+    /// The model stopped because its output was filtered
+    ContentFiltered {
+        message: String,
+    },
+    /// The request never got an HTTP response
+    Network {
+        message: String,
+    },
+    /// The response could not be understood (bad JSON, unknown finish reason, ...)
+    InvalidResponse {
+        message: String,
+    },
+    /// A spending limit would be exceeded, so the request was never sent
+    BudgetExceeded {
+        message: String,
+        budget: String,
+        limit: f64,
+        spent: f64,
+        estimated_cost: f64,
+    },
+    /// Anything on our side, such as an unknown model
+    Internal {
+        message: String,
+    },
+}
+
+impl GenerationError {
+    /// Classify a non-2xx response from a provider
+    pub fn from_http_response(provider: &str, response: &HttpResponse) -> Self {
+        let body: Option<Value> = response
+            .body
+            .as_ref()
+            .and_then(|body| serde_json::from_slice(body).ok());
+
+        // OpenRouter and Anthropic both wrap failures as {"error": {"message": ...}}
+        let details = body.map(|body| match body.get("error") {
+            Some(error) => error.clone(),
+            None => body,
+        });
+        let message = details
+            .as_ref()
+            .and_then(|details| details["message"].as_str())
+            .map(String::from)
+            .unwrap_or_else(|| format!("HTTP status {}", response.status));
+
+        match response.status {
+            401 | 403 => GenerationError::Auth {
+                provider: provider.to_string(),
+                message,
+            },
+            429 => GenerationError::RateLimited {
+                provider: provider.to_string(),
+                message,
+                retry_after: response
+                    .headers
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case("retry-after"))
+                    .and_then(|(_, value)| value.trim().parse().ok()),
+            },
+            status => GenerationError::Provider {
+                provider: provider.to_string(),
+                status,
+                message,
+                details,
+            },
+        }
+    }
+
+    /// Build an error from an error object embedded in a 200 response
+    pub fn from_error_body(provider: &str, error: &Value) -> Self {
+        let message = error["message"]
+            .as_str()
+            .unwrap_or("Provider reported an error")
+            .to_string();
+        // OpenRouter sends an HTTP-style code, Anthropic an error type
+        let status = error["code"]
+            .as_u64()
+            .or_else(|| match error["type"].as_str() {
+                Some("rate_limit_error") => Some(429),
+                Some("authentication_error") | Some("permission_error") => Some(401),
+                _ => None,
+            })
+            .unwrap_or(500) as u16;
+
+        match status {
+            401 | 403 => GenerationError::Auth {
+                provider: provider.to_string(),
+                message,
+            },
+            429 => GenerationError::RateLimited {
+                provider: provider.to_string(),
+                message,
+                retry_after: None,
+            },
+            _ => GenerationError::Provider {
+                provider: provider.to_string(),
+                status,
+                message,
+                details: Some(error.clone()),
+            },
+        }
+    }
+
+    /// Explain a 2xx body that did not parse, which is often an embedded error object
+    pub fn from_unparseable_body(provider: &str, body: &[u8], error: serde_json::Error) -> Self {
+        match serde_json::from_slice::<Value>(body) {
+            Ok(value) if value.get("error").is_some() => {
+                GenerationError::from_error_body(provider, &value["error"])
+            }
+            _ => GenerationError::InvalidResponse {
+                message: format!("Failed to parse {} response: {}", provider, error),
+            },
+        }
+    }
+
+    /// Recover a `GenerationError` from a boxed error, treating anything else as internal
+    pub fn from_boxed(error: &(dyn std::error::Error + 'static)) -> Self {
+        match error.downcast_ref::<GenerationError>() {
+            Some(error) => error.clone(),
+            None => GenerationError::Internal {
+                message: error.to_string(),
+            },
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            GenerationError::Provider { .. } => "provider",
+            GenerationError::RateLimited { .. } => "rate_limited",
+            GenerationError::Auth { .. } => "auth",
+            GenerationError::ContentFiltered { .. } => "content_filtered",
+            GenerationError::Network { .. } => "network",
+            GenerationError::InvalidResponse { .. } => "invalid_response",
+            GenerationError::BudgetExceeded { .. } => "budget_exceeded",
+            GenerationError::Internal { .. } => "internal",
+        }
+    }
+
+    /// Whether sending the same request again may succeed
+    pub fn retryable(&self) -> bool {
+        match self {
+            GenerationError::Provider { status, .. } => *status == 408 || *status >= 500,
+            GenerationError::RateLimited { .. } | GenerationError::Network { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// The human readable message, as reported by the provider where there is one
+    pub fn message(&self) -> &str {
+        match self {
+            GenerationError::Provider { message, .. }
+            | GenerationError::RateLimited { message, .. }
+            | GenerationError::Auth { message, .. }
+            | GenerationError::ContentFiltered { message }
+            | GenerationError::Network { message }
+            | GenerationError::InvalidResponse { message }
+            | GenerationError::BudgetExceeded { message, .. }
+            | GenerationError::Internal { message } => message,
+        }
+    }
+}
+
+impl fmt::Display for GenerationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GenerationError::Provider {
+                provider, status, ..
+            } => write!(
+                f,
+                "{} API error (HTTP status {}): {}",
+                provider,
+                status,
+                self.message()
+            ),
+            GenerationError::RateLimited { provider, .. } => {
+                write!(f, "{} rate limit hit: {}", provider, self.message())
+            }
+            GenerationError::Auth { provider, .. } => {
+                write!(
+                    f,
+                    "{} rejected the credentials: {}",
+                    provider,
+                    self.message()
+                )
+            }
+            GenerationError::ContentFiltered { .. } => {
+                write!(f, "Response was filtered: {}", self.message())
+            }
+            GenerationError::Network { .. } => {
+                write!(f, "HTTP request failed: {}", self.message())
+            }
+            GenerationError::InvalidResponse { .. } => {
+                write!(f, "Invalid provider response: {}", self.message())
+            }
+            GenerationError::BudgetExceeded { .. } | GenerationError::Internal { .. } => {
+                write!(f, "{}", self.message())
+            }
+        }
+    }
+}
+
+impl std::error::Error for GenerationError {}
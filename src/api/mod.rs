@@ -1 +1,166 @@
+pub mod anthropic;
+pub mod error;
+pub mod openai_compatible;
 pub mod openrouter;
+pub mod retry;
+
+use crate::bindings::ntwk::theater::runtime::log;
+use crate::messages::{AssistantMessage, GenerationParams, Message, ModelInfo};
+use anthropic::AnthropicClient;
+use mcp_protocol::types::tool::Tool;
+use openai_compatible::{OpenAiCompatibleClient, OpenAiCompatibleConfig};
+use openrouter::OpenRouterClient;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+
+pub const OPENROUTER_PROVIDER: &str = "openrouter";
+pub const ANTHROPIC_PROVIDER: &str = "anthropic";
+pub const OPENAI_COMPATIBLE_PROVIDER: &str = "openai-compatible";
+
+/// Per-million-token prices for a model, in USD
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ModelPricing {
+    pub input_cost_per_million_tokens: Option<f64>,
+    pub output_cost_per_million_tokens: Option<f64>,
+}
+
+/// Common interface for every LLM backend the actor can talk to.
+///
+/// Each provider owns the `ModelInfo` entries whose `provider` field selects it.
+pub trait LlmProvider {
+    /// Provider identifier, matching `ModelInfo.provider`
+    fn name(&self) -> &str;
+
+    fn model_configs(&self) -> &[ModelInfo];
+
+    /// `params` are the fully resolved generation settings; providers fall back to
+    /// `ModelInfo.max_tokens` when no limit is set.
+    fn generate_response(
+        &self,
+        messages: Vec<Message>,
+        model_id: String,
+        available_tools: Option<Vec<Tool>>,
+        params: &GenerationParams,
+    ) -> Result<AssistantMessage, Box<dyn std::error::Error>>;
+
+    fn list_available_models(&self) -> Result<Vec<ModelInfo>, Box<dyn std::error::Error>> {
+        Ok(self.model_configs().to_vec())
+    }
+
+    fn model_info(&self, model_id: &str) -> Option<&ModelInfo> {
+        self.model_configs().iter().find(|m| m.id == model_id)
+    }
+
+    fn pricing(&self, model_id: &str) -> Option<ModelPricing> {
+        self.model_info(model_id).map(|m| ModelPricing {
+            input_cost_per_million_tokens: m.input_cost_per_million_tokens,
+            output_cost_per_million_tokens: m.output_cost_per_million_tokens,
+        })
+    }
+
+    /// The model's configured default generation params
+    fn default_params(&self, model_id: &str) -> GenerationParams {
+        self.model_info(model_id)
+            .and_then(|m| m.generation_params.clone())
+            .unwrap_or_default()
+    }
+
+    /// Models to try, in order, when this one is unavailable
+    fn fallback_models(&self, model_id: &str) -> Vec<String> {
+        self.model_info(model_id)
+            .and_then(|m| m.fallback_models.clone())
+            .unwrap_or_default()
+    }
+
+    fn supports_tools(&self, model_id: &str) -> bool {
+        self.model_info(model_id)
+            .map(|m| m.tools_enabled)
+            .unwrap_or(false)
+    }
+}
+
+/// The configured providers, with each model routed by its `ModelInfo.provider`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Providers {
+    pub openrouter: OpenRouterClient,
+    pub anthropic: Option<AnthropicClient>,
+    pub openai_compatible: Option<OpenAiCompatibleClient>,
+}
+
+impl Providers {
+    pub fn new(
+        openrouter_api_key: String,
+        anthropic_api_key: Option<String>,
+        openai_compatible_config: Option<OpenAiCompatibleConfig>,
+        model_configs: Vec<ModelInfo>,
+    ) -> Self {
+        let mut openrouter_models = Vec::new();
+        let mut anthropic_models = Vec::new();
+        let mut openai_compatible_models = Vec::new();
+
+        for model in model_configs {
+            match model.provider.as_deref() {
+                Some(ANTHROPIC_PROVIDER) => anthropic_models.push(model),
+                Some(OPENAI_COMPATIBLE_PROVIDER) => openai_compatible_models.push(model),
+                _ => openrouter_models.push(model),
+            }
+        }
+
+        if anthropic_api_key.is_none() && !anthropic_models.is_empty() {
+            log("Warning: Anthropic models configured without an anthropic_api_key");
+        }
+        if openai_compatible_config.is_none() && !openai_compatible_models.is_empty() {
+            log("Warning: OpenAI-compatible models configured without an openai_compatible endpoint");
+        }
+
+        Self {
+            openrouter: OpenRouterClient::new(openrouter_api_key, openrouter_models),
+            anthropic: anthropic_api_key.map(|key| AnthropicClient::new(key, anthropic_models)),
+            openai_compatible: openai_compatible_config
+                .map(|config| OpenAiCompatibleClient::new(config, openai_compatible_models)),
+        }
+    }
+
+    fn all(&self) -> Vec<&dyn LlmProvider> {
+        let mut providers: Vec<&dyn LlmProvider> = vec![&self.openrouter];
+        if let Some(anthropic) = &self.anthropic {
+            providers.push(anthropic);
+        }
+        if let Some(openai_compatible) = &self.openai_compatible {
+            providers.push(openai_compatible);
+        }
+        providers
+    }
+
+    /// Find the provider that serves the given model
+    pub fn for_model(
+        &self,
+        model_id: &str,
+    ) -> Result<&dyn LlmProvider, Box<dyn std::error::Error>> {
+        self.all()
+            .into_iter()
+            .find(|provider| provider.model_info(model_id).is_some())
+            .ok_or_else(|| format!("No configured provider serves model {}", model_id).into())
+    }
+
+    /// All models across providers, with `provider` filled in
+    pub fn list_available_models(&self) -> Result<Vec<ModelInfo>, Box<dyn std::error::Error>> {
+        let mut models = Vec::new();
+        for provider in self.all() {
+            for mut model in provider.list_available_models()? {
+                if model.provider.is_none() {
+                    model.provider = Some(provider.name().to_string());
+                }
+                models.push(model);
+            }
+        }
+        Ok(models)
+    }
+}
+
+// Generate a unique ID for an assistant message from its content
+pub(crate) fn message_id(content: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(content.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
@@ -0,0 +1,63 @@
+use crate::api::openrouter::OpenRouterClient;
+use crate::api::{LlmProvider, OPENAI_COMPATIBLE_PROVIDER};
+use crate::bindings::ntwk::theater::runtime::log;
+use crate::messages::{AssistantMessage, GenerationParams, Message, ModelInfo};
+use mcp_protocol::types::tool::Tool;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OpenAiCompatibleConfig {
+    pub base_url: String, // e.g. http://localhost:8080/v1 for llama.cpp or ollama
+    pub api_key: Option<String>, // Most local servers do not require one
+}
+
+/// Client for any endpoint that speaks the OpenAI chat completions protocol.
+///
+/// OpenRouter uses the same wire format, so requests are delegated to an
+/// `OpenRouterClient` pointed at the configured base URL.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OpenAiCompatibleClient {
+    base_url: String,
+    inner: OpenRouterClient,
+}
+
+impl OpenAiCompatibleClient {
+    pub fn new(config: OpenAiCompatibleConfig, model_configs: Vec<ModelInfo>) -> Self {
+        let base_url = config.base_url.trim_end_matches('/').to_string();
+        log(&format!(
+            "Initializing OpenAI-compatible client for {}",
+            base_url
+        ));
+
+        Self {
+            inner: OpenRouterClient::with_url(
+                config.api_key.unwrap_or_default(),
+                base_url.clone(),
+                model_configs,
+            ),
+            base_url,
+        }
+    }
+}
+
+impl LlmProvider for OpenAiCompatibleClient {
+    fn name(&self) -> &str {
+        OPENAI_COMPATIBLE_PROVIDER
+    }
+
+    fn model_configs(&self) -> &[ModelInfo] {
+        self.inner.model_configs()
+    }
+
+    fn generate_response(
+        &self,
+        messages: Vec<Message>,
+        model_id: String,
+        available_tools: Option<Vec<Tool>>,
+        params: &GenerationParams,
+    ) -> Result<AssistantMessage, Box<dyn std::error::Error>> {
+        log(&format!("Sending request to {}", self.base_url));
+        self.inner
+            .generate_response(messages, model_id, available_tools, params)
+    }
+}
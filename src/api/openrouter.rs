@@ -1,12 +1,16 @@
+use crate::api::error::GenerationError;
+use crate::api::{message_id, LlmProvider, OPENROUTER_PROVIDER};
 use crate::bindings::ntwk::theater::http_client::{send_http, HttpRequest};
 use crate::bindings::ntwk::theater::runtime::log;
 use crate::messages::{
-    openrouter::{OpenRouterMessage, OpenRouterRequest, OpenRouterResponse},
-    AssistantMessage, Message, ModelInfo,
+    openrouter::{
+        OpenRouterMessage, OpenRouterRequest, OpenRouterResponse, OpenRouterTool,
+        OpenRouterToolCall,
+    },
+    AssistantMessage, GenerationParams, Message, ModelInfo, ToolCall,
 };
 use mcp_protocol::types::tool::Tool;
 use serde::{Deserialize, Serialize};
-use sha1::{Digest, Sha1};
 
 // OpenRouter client implementation
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -40,40 +44,58 @@ impl OpenRouterClient {
         }
     }
 
-    pub fn list_available_models(&self) -> Result<Vec<ModelInfo>, Box<dyn std::error::Error>> {
-        Ok(self.model_configs.clone())
+    /// Point the client at any OpenAI-compatible chat completions endpoint
+    pub fn with_url(api_key: String, url: String, model_configs: Vec<ModelInfo>) -> Self {
+        Self {
+            api_key,
+            url,
+            model_configs,
+        }
     }
 
-    pub fn generate_response(
+    fn require_model(&self, model_id: &str) -> Result<&ModelInfo, Box<dyn std::error::Error>> {
+        Ok(self.model_info(model_id).ok_or("Model not found")?)
+    }
+
+    fn build_request(
         &self,
         messages: Vec<Message>,
-        model_id: String,
+        model_id: &str,
         available_tools: Option<Vec<Tool>>,
-    ) -> Result<AssistantMessage, Box<dyn std::error::Error>> {
-        let model_info = self
-            .model_configs
-            .iter()
-            .find(|m| m.id == model_id)
-            .ok_or("Model not found")?;
+        params: &GenerationParams,
+    ) -> Result<HttpRequest, Box<dyn std::error::Error>> {
+        let model_info = self.require_model(model_id)?;
 
         // Convert our internal message format to OpenRouter format
         let openrouter_messages: Vec<OpenRouterMessage> = messages
             .iter()
             .map(|msg| match msg {
+                Message::System(msg) => OpenRouterMessage {
+                    role: "system".to_string(),
+                    content: msg.content.clone(),
+                    tool_call_id: None,
+                    tool_calls: None,
+                },
                 Message::User(msg) => OpenRouterMessage {
                     role: "user".to_string(),
                     content: msg.content.clone(),
                     tool_call_id: None,
+                    tool_calls: None,
                 },
                 Message::Assistant(msg) => OpenRouterMessage {
                     role: "assistant".to_string(),
                     content: msg.content.clone(),
                     tool_call_id: None,
+                    tool_calls: msg
+                        .tool_calls
+                        .as_ref()
+                        .map(|calls| calls.iter().map(OpenRouterToolCall::from).collect()),
                 },
                 Message::Tool(msg) => OpenRouterMessage {
                     role: "tool".to_string(),
                     content: msg.content.clone(),
                     tool_call_id: Some(msg.tool_call_id.clone()),
+                    tool_calls: None,
                 },
             })
             .collect();
@@ -82,45 +104,53 @@ impl OpenRouterClient {
         let url = format!("{}/chat/completions", self.url.clone());
 
         let tools = if model_info.tools_enabled {
-            available_tools
+            available_tools.map(|tools| tools.iter().map(OpenRouterTool::from).collect())
         } else {
             None
         };
 
         // Create request body with model-specific parameters
         let request_body = OpenRouterRequest {
-            model: model_id.clone(),
+            model: model_id.to_string(),
             messages: openrouter_messages,
             tools,
+            temperature: params.temperature,
+            max_tokens: Some(params.max_tokens.unwrap_or(model_info.max_tokens)),
+            top_p: params.top_p,
+            stop: params.stop.clone(),
         };
 
         // Prepare the request body - log it for debugging
         let request_body_json = serde_json::to_string(&request_body).unwrap_or_default();
         log(&format!("OpenRouter request body: {}", request_body_json));
 
-        // Set up headers
-        let headers = vec![
-            (
+        // Set up headers; local OpenAI-compatible servers often run without a key
+        let mut headers = vec![("Content-Type".to_string(), "application/json".to_string())];
+        if !self.api_key.is_empty() {
+            headers.push((
                 "Authorization".to_string(),
                 format!("Bearer {}", self.api_key),
-            ),
-            ("Content-Type".to_string(), "application/json".to_string()),
-        ];
+            ));
+        }
 
         // Create the HTTP request
-        let request = HttpRequest {
+        Ok(HttpRequest {
             method: "POST".to_string(),
             uri: url,
             headers,
             body: Some(serde_json::to_vec(&request_body)?),
-        };
+        })
+    }
 
+    // Send a request and return the body of a successful response
+    fn send_request(&self, request: &HttpRequest) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
         log("Sending OpenRouter request...");
         log(&format!("Request: {:?}", request));
 
         // Send the request
-        let http_response =
-            send_http(&request).map_err(|e| format!("HTTP request failed: {}", e))?;
+        let http_response = send_http(request).map_err(|e| GenerationError::Network {
+            message: e.to_string(),
+        })?;
 
         // Log the response for debugging
         log(&format!(
@@ -132,12 +162,16 @@ impl OpenRouterClient {
         if http_response.status < 200 || http_response.status >= 300 {
             log(&format!("OpenRouter response: {:?}", http_response));
             return Err(
-                format!("OpenRouter API error: HTTP status {}", http_response.status).into(),
+                GenerationError::from_http_response(OPENROUTER_PROVIDER, &http_response).into(),
             );
         }
 
         // Check if we have a response body
-        let body = http_response.body.ok_or("No response body")?;
+        let body = http_response
+            .body
+            .ok_or_else(|| GenerationError::InvalidResponse {
+                message: "No response body".to_string(),
+            })?;
 
         // Log a truncated version of the response body for debugging
         let body_preview =
@@ -147,35 +181,72 @@ impl OpenRouterClient {
             body_preview
         ));
 
+        Ok(body)
+    }
+}
+
+impl LlmProvider for OpenRouterClient {
+    fn name(&self) -> &str {
+        OPENROUTER_PROVIDER
+    }
+
+    fn model_configs(&self) -> &[ModelInfo] {
+        &self.model_configs
+    }
+
+    fn generate_response(
+        &self,
+        messages: Vec<Message>,
+        model_id: String,
+        available_tools: Option<Vec<Tool>>,
+        params: &GenerationParams,
+    ) -> Result<AssistantMessage, Box<dyn std::error::Error>> {
+        let model_info = self.require_model(&model_id)?;
+        let request = self.build_request(messages, &model_id, available_tools, params)?;
+        let body = self.send_request(&request)?;
+
         // Parse the response
-        let response: OpenRouterResponse = serde_json::from_slice(&body)?;
+        let response: OpenRouterResponse = serde_json::from_slice(&body)
+            .map_err(|e| GenerationError::from_unparseable_body(OPENROUTER_PROVIDER, &body, e))?;
 
         // Log the parsed response
         log(&format!("Parsed OpenRouter response: {:?}", response));
 
         // Extract the first choice
         if response.choices.is_empty() {
-            return Err("No response choices".into());
+            return Err(GenerationError::InvalidResponse {
+                message: "No response choices".to_string(),
+            }
+            .into());
         }
 
         let choice = &response.choices[0];
-        let content = choice.message.content.clone();
-
-        // Generate a unique ID for the message
-        let mut hasher = Sha1::new();
-        hasher.update(content.as_bytes());
-        let id = format!("{:x}", hasher.finalize());
+        let content = choice.message.content.clone().unwrap_or_default();
+        let tool_calls = choice
+            .message
+            .tool_calls
+            .as_ref()
+            .map(|calls| calls.iter().map(ToolCall::from).collect::<Vec<_>>());
+
+        if let Some(calls) = &tool_calls {
+            log(&format!("Model requested {} tool calls", calls.len()));
+        }
 
         // Create our message
         let openrouter_message = AssistantMessage {
+            id: message_id(&content),
             content,
-            id,
             model: response.model.clone(),
             finish_reason: choice.finish_reason.clone(),
             native_finish_reason: response.native_finish_reason.clone(),
             usage: response.usage.clone(),
             input_cost_per_million_tokens: model_info.input_cost_per_million_tokens,
             output_cost_per_million_tokens: model_info.output_cost_per_million_tokens,
+            tool_calls,
+            continuation_of: None,
+            truncated: false,
+            error: None,
+            requested_model: None,
         };
 
         // Wrap in the enum
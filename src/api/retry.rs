@@ -0,0 +1,52 @@
+use crate::api::error::GenerationError;
+use serde::{Deserialize, Serialize};
+
+/// How often, and how patiently, failed requests are retried before giving up on a model
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct RetryConfig {
+    pub max_retries: u32,
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            initial_backoff_ms: 1000,
+            max_backoff_ms: 30000,
+        }
+    }
+}
+
+impl RetryConfig {
+    /// How long to wait before retry number `attempt` (starting at 0), or `None` if the
+    /// error should not be retried at all.
+    ///
+    /// A `Retry-After` from the provider wins over the exponential backoff, but a wait
+    /// longer than `max_backoff_ms` is not worth blocking the actor for.
+    pub fn delay_for(&self, attempt: u32, error: &GenerationError) -> Option<u64> {
+        if attempt >= self.max_retries || !error.retryable() {
+            return None;
+        }
+
+        if let GenerationError::RateLimited {
+            retry_after: Some(seconds),
+            ..
+        } = error
+        {
+            let delay = seconds.saturating_mul(1000);
+            return if delay <= self.max_backoff_ms {
+                Some(delay)
+            } else {
+                None
+            };
+        }
+
+        let backoff = self
+            .initial_backoff_ms
+            .saturating_mul(1u64 << attempt.min(32));
+        Some(backoff.min(self.max_backoff_ms))
+    }
+}
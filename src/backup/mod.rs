@@ -0,0 +1,260 @@
+use crate::bindings::ntwk::theater::runtime::log;
+use crate::bindings::ntwk::theater::store::{self, ContentRef};
+use crate::bindings::ntwk::theater::timing;
+use crate::messages::store::{parse_children_label, parse_roots_label};
+use crate::messages::{ChainEntry, ChatInfo};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// A self-contained copy of a store: every label, the content it points at, and every
+/// chain entry that can be reached from a chat
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Archive {
+    pub version: u32,
+    pub created_at: u64,                  // Milliseconds since the epoch
+    pub chats: Vec<String>,               // The `chats` list
+    pub labels: BTreeMap<String, String>, // Label -> content hash
+    pub contents: BTreeMap<String, ArchivedContent>, // Keyed by content hash
+}
+
+/// Stored bytes, kept verbatim so their hashes can be checked on restore
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchivedContent {
+    Text(String),
+    Hex(String), // Anything that is not UTF-8
+}
+
+impl ArchivedContent {
+    fn from_bytes(bytes: Vec<u8>) -> Self {
+        match String::from_utf8(bytes) {
+            Ok(text) => ArchivedContent::Text(text),
+            Err(e) => ArchivedContent::Hex(
+                e.into_bytes()
+                    .iter()
+                    .map(|byte| format!("{:02x}", byte))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        match self {
+            ArchivedContent::Text(text) => Ok(text.as_bytes().to_vec()),
+            ArchivedContent::Hex(hex) => {
+                if hex.len() % 2 != 0 {
+                    return Err("hex content has an odd length".to_string());
+                }
+                (0..hex.len())
+                    .step_by(2)
+                    .map(|i| {
+                        hex.get(i..i + 2)
+                            .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                            .ok_or_else(|| "invalid hex content".to_string())
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// What a restore loaded, and into which store
+#[derive(Serialize, Debug, Clone)]
+pub struct RestoreReport {
+    pub store_id: String,
+    pub chats: usize,
+    pub labels: usize,
+    pub contents: usize,
+}
+
+/// Why an archive was not restored. `problems` lists every label and content hash that
+/// failed verification.
+#[derive(Debug, Clone)]
+pub struct RestoreError {
+    pub message: String,
+    pub problems: Vec<String>,
+}
+
+impl RestoreError {
+    fn new(message: impl Into<String>, problems: Vec<String>) -> Self {
+        Self {
+            message: message.into(),
+            problems,
+        }
+    }
+}
+
+impl fmt::Display for RestoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if !self.problems.is_empty() {
+            write!(f, ": {}", self.problems.join("; "))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RestoreError {}
+
+/// Copy everything in the store that a chat can still reach into an archive. Old versions
+/// of labeled documents are left behind.
+pub fn create(store_id: &str) -> Result<Archive, Box<dyn std::error::Error>> {
+    let stored: HashSet<String> = store::list_all_content(store_id)?
+        .into_iter()
+        .map(|content_ref| content_ref.hash)
+        .collect();
+
+    let mut labels = BTreeMap::new();
+    for label in store::list_labels(store_id)? {
+        if let Some(content_ref) = store::get_by_label(store_id, &label)? {
+            labels.insert(label, content_ref.hash);
+        }
+    }
+
+    let chats: Vec<String> = match labels.get("chats") {
+        Some(hash) => serde_json::from_slice(&get(store_id, hash)?)?,
+        None => Vec::new(),
+    };
+
+    // Every labeled document, plus the chain entries the chats and indexes point at
+    let mut pending: Vec<String> = labels.values().cloned().collect();
+    for chat_id in &chats {
+        if let Some(hash) = labels.get(&format!("chat_{}", chat_id)) {
+            let chat_info: ChatInfo = serde_json::from_slice(&get(store_id, hash)?)?;
+            pending.extend(chat_info.head);
+            pending.extend(chat_info.pinned_messages);
+        }
+    }
+    for (label, hash) in &labels {
+        if parse_children_label(label).is_some() || parse_roots_label(label).is_some() {
+            let ids: Vec<String> = serde_json::from_slice(&get(store_id, hash)?)?;
+            pending.extend(ids);
+        }
+    }
+
+    let mut contents = BTreeMap::new();
+    while let Some(hash) = pending.pop() {
+        if contents.contains_key(&hash) {
+            continue;
+        }
+        if !stored.contains(&hash) {
+            log(&format!("[WARN] Backup skipping missing content {}", hash));
+            continue;
+        }
+
+        let bytes = get(store_id, &hash)?;
+        if let Ok(entry) = serde_json::from_slice::<ChainEntry>(&bytes) {
+            pending.extend(entry.parents);
+        }
+        contents.insert(hash, ArchivedContent::from_bytes(bytes));
+    }
+
+    log(&format!(
+        "Backed up {} labels and {} of {} stored items",
+        labels.len(),
+        contents.len(),
+        stored.len()
+    ));
+
+    Ok(Archive {
+        version: ARCHIVE_VERSION,
+        created_at: timing::now(),
+        chats,
+        labels,
+        contents,
+    })
+}
+
+/// Load an archive into a new store. Each item is stored and the hash the store computes
+/// must match the archived one; labels are only written once everything has checked out.
+pub fn restore(archive: &Archive) -> Result<RestoreReport, RestoreError> {
+    if archive.version != ARCHIVE_VERSION {
+        return Err(RestoreError::new(
+            format!("Unsupported archive version {}", archive.version),
+            Vec::new(),
+        ));
+    }
+
+    let mut problems = Vec::new();
+    for (label, hash) in &archive.labels {
+        if !archive.contents.contains_key(hash) {
+            problems.push(format!(
+                "label {} points at missing content {}",
+                label, hash
+            ));
+        }
+    }
+    for chat_id in &archive.chats {
+        if !archive.labels.contains_key(&format!("chat_{}", chat_id)) {
+            problems.push(format!("chat {} has no chat info", chat_id));
+        }
+    }
+    if !problems.is_empty() {
+        return Err(RestoreError::new("Archive is incomplete", problems));
+    }
+
+    let store_id = store::new()
+        .map_err(|e| RestoreError::new(format!("Failed to create store: {}", e), Vec::new()))?;
+
+    for (hash, content) in &archive.contents {
+        let bytes = match content.to_bytes() {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                problems.push(format!("{}: {}", hash, e));
+                continue;
+            }
+        };
+        match store::store(&store_id, &bytes) {
+            Ok(content_ref) if &content_ref.hash == hash => {}
+            Ok(content_ref) => {
+                problems.push(format!("{}: content hashes to {}", hash, content_ref.hash))
+            }
+            Err(e) => problems.push(format!("{}: {}", hash, e)),
+        }
+    }
+    if !problems.is_empty() {
+        return Err(RestoreError::new(
+            format!(
+                "Archive failed verification; store {} left unused",
+                store_id
+            ),
+            problems,
+        ));
+    }
+
+    for (label, hash) in &archive.labels {
+        let content_ref = ContentRef { hash: hash.clone() };
+        store::label(&store_id, label, &content_ref).map_err(|e| {
+            RestoreError::new(
+                format!("Failed to restore label {}: {}", label, e),
+                Vec::new(),
+            )
+        })?;
+    }
+
+    log(&format!(
+        "Restored {} labels and {} items into store {}",
+        archive.labels.len(),
+        archive.contents.len(),
+        store_id
+    ));
+
+    Ok(RestoreReport {
+        store_id,
+        chats: archive.chats.len(),
+        labels: archive.labels.len(),
+        contents: archive.contents.len(),
+    })
+}
+
+fn get(store_id: &str, hash: &str) -> Result<Vec<u8>, String> {
+    store::get(
+        store_id,
+        &ContentRef {
+            hash: hash.to_string(),
+        },
+    )
+}
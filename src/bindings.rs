@@ -5584,6 +5584,272 @@ pub mod ntwk {
                 }
             }
         }
+        /// # Timing Interface
+        ///
+        /// Provides time-related functions for actors to get the current time and control execution timing.
+        ///
+        /// ## Purpose
+        ///
+        /// The timing interface gives actors access to time information and timing control
+        /// within the Theater runtime. It allows actors to:
+        /// - Get the current time
+        /// - Pause execution for specific durations
+        /// - Delay execution until specific points in time
+        ///
+        /// ## Example
+        ///
+        /// ```rust
+        /// use ntwk::theater::timing;
+        ///
+        /// async fn example() -> Result<(), String> {
+        ///     // Get the current time
+        ///     let now = timing::now();
+        ///     println!("Current time: {}", now);
+        ///
+        ///     // Sleep for 500 milliseconds
+        ///     timing::sleep(500)?;
+        ///
+        ///     // Wait until a specific future time
+        ///     let five_seconds_later = now + 5000;
+        ///     timing::deadline(five_seconds_later)?;
+        ///
+        ///     Ok(())
+        /// }
+        /// ```
+        ///
+        /// ## Security
+        ///
+        /// The timing operations are managed by the Theater runtime, which may enforce:
+        /// - Rate limits on sleep operations to prevent resource exhaustion
+        /// - Maximum duration limits to prevent indefinite blocking
+        /// - Tracking and reporting of sleep patterns in the event chain
+        ///
+        /// ## Implementation Notes
+        ///
+        /// When actors call timing functions, the WebAssembly execution is suspended without
+        /// blocking the entire runtime. This allows the runtime to continue processing other
+        /// actors while an actor is waiting.
+        #[allow(dead_code, async_fn_in_trait, unused_imports, clippy::all)]
+        pub mod timing {
+            #[used]
+            #[doc(hidden)]
+            static __FORCE_SECTION_REF: fn() = super::super::super::__link_custom_section_describing_imports;
+            use super::super::super::_rt;
+            #[allow(unused_unsafe, clippy::all)]
+            /// # Get current time
+            ///
+            /// Returns the current time in milliseconds since the UNIX epoch (January 1, 1970 UTC).
+            ///
+            /// ## Returns
+            ///
+            /// The current timestamp in milliseconds
+            ///
+            /// ## Example
+            ///
+            /// ```rust
+            /// use ntwk::theater::timing;
+            ///
+            /// // Get current timestamp
+            /// let now = timing::now();
+            ///
+            /// // Convert to seconds
+            /// let seconds_since_epoch = now / 1000;
+            /// ```
+            ///
+            /// ## Implementation Notes
+            ///
+            /// The time value is consistent across the entire Theater runtime, ensuring that
+            /// all actors have a synchronized view of time.
+            pub fn now() -> u64 {
+                unsafe {
+                    #[cfg(target_arch = "wasm32")]
+                    #[link(wasm_import_module = "ntwk:theater/timing")]
+                    unsafe extern "C" {
+                        #[link_name = "now"]
+                        fn wit_import0() -> i64;
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    unsafe extern "C" fn wit_import0() -> i64 {
+                        unreachable!()
+                    }
+                    let ret = unsafe { wit_import0() };
+                    ret as u64
+                }
+            }
+            #[allow(unused_unsafe, clippy::all)]
+            /// # Pause execution
+            ///
+            /// Pauses the execution of the actor for the specified number of milliseconds.
+            ///
+            /// ## Parameters
+            ///
+            /// * `duration` - Number of milliseconds to sleep
+            ///
+            /// ## Returns
+            ///
+            /// * `Ok(_)` - Sleep completed successfully
+            /// * `Err(string)` - Error message if sleep was interrupted or not allowed
+            ///
+            /// ## Example
+            ///
+            /// ```rust
+            /// use ntwk::theater::timing;
+            ///
+            /// // Sleep for 1 second
+            /// timing::sleep(1000)?;
+            ///
+            /// // Sleep for 100ms
+            /// timing::sleep(100)?;
+            /// ```
+            ///
+            /// ## Security
+            ///
+            /// The runtime may enforce limits on how long an actor can sleep to prevent
+            /// resource exhaustion or denial of service. Sleep operations are recorded
+            /// in the actor's event chain.
+            pub fn sleep(duration: u64) -> Result<(), _rt::String> {
+                unsafe {
+                    #[cfg_attr(target_pointer_width = "64", repr(align(8)))]
+                    #[cfg_attr(target_pointer_width = "32", repr(align(4)))]
+                    struct RetArea(
+                        [::core::mem::MaybeUninit<
+                            u8,
+                        >; 3 * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let mut ret_area = RetArea(
+                        [::core::mem::MaybeUninit::uninit(); 3
+                            * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let ptr0 = ret_area.0.as_mut_ptr().cast::<u8>();
+                    #[cfg(target_arch = "wasm32")]
+                    #[link(wasm_import_module = "ntwk:theater/timing")]
+                    unsafe extern "C" {
+                        #[link_name = "sleep"]
+                        fn wit_import1(_: i64, _: *mut u8);
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    unsafe extern "C" fn wit_import1(_: i64, _: *mut u8) {
+                        unreachable!()
+                    }
+                    unsafe { wit_import1(_rt::as_i64(&duration), ptr0) };
+                    let l2 = i32::from(*ptr0.add(0).cast::<u8>());
+                    let result6 = match l2 {
+                        0 => {
+                            let e = ();
+                            Ok(e)
+                        }
+                        1 => {
+                            let e = {
+                                let l3 = *ptr0
+                                    .add(::core::mem::size_of::<*const u8>())
+                                    .cast::<*mut u8>();
+                                let l4 = *ptr0
+                                    .add(2 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<usize>();
+                                let len5 = l4;
+                                let bytes5 = _rt::Vec::from_raw_parts(
+                                    l3.cast(),
+                                    len5,
+                                    len5,
+                                );
+                                _rt::string_lift(bytes5)
+                            };
+                            Err(e)
+                        }
+                        _ => _rt::invalid_enum_discriminant(),
+                    };
+                    result6
+                }
+            }
+            #[allow(unused_unsafe, clippy::all)]
+            /// # Wait until specific time
+            ///
+            /// Pauses execution until the specified timestamp is reached.
+            ///
+            /// ## Parameters
+            ///
+            /// * `timestamp` - Target time in milliseconds since UNIX epoch
+            ///
+            /// ## Returns
+            ///
+            /// * `Ok(_)` - Deadline was reached successfully
+            /// * `Err(string)` - Error message if the wait was interrupted or not allowed
+            ///
+            /// ## Example
+            ///
+            /// ```rust
+            /// use ntwk::theater::timing;
+            ///
+            /// // Wait until a specific time
+            /// let target_time = 1672531200000; // Jan 1, 2023 00:00:00 UTC
+            /// timing::deadline(target_time)?;
+            ///
+            /// // Wait until 10 seconds from now
+            /// let now = timing::now();
+            /// let ten_seconds_later = now + 10000;
+            /// timing::deadline(ten_seconds_later)?;
+            /// ```
+            ///
+            /// ## Implementation Notes
+            ///
+            /// - If the specified timestamp is in the past, the function returns immediately
+            /// - The runtime may reject excessive deadline values that are too far in the future
+            /// - Deadline operations are recorded in the actor's event chain
+            pub fn deadline(timestamp: u64) -> Result<(), _rt::String> {
+                unsafe {
+                    #[cfg_attr(target_pointer_width = "64", repr(align(8)))]
+                    #[cfg_attr(target_pointer_width = "32", repr(align(4)))]
+                    struct RetArea(
+                        [::core::mem::MaybeUninit<
+                            u8,
+                        >; 3 * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let mut ret_area = RetArea(
+                        [::core::mem::MaybeUninit::uninit(); 3
+                            * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let ptr0 = ret_area.0.as_mut_ptr().cast::<u8>();
+                    #[cfg(target_arch = "wasm32")]
+                    #[link(wasm_import_module = "ntwk:theater/timing")]
+                    unsafe extern "C" {
+                        #[link_name = "deadline"]
+                        fn wit_import1(_: i64, _: *mut u8);
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    unsafe extern "C" fn wit_import1(_: i64, _: *mut u8) {
+                        unreachable!()
+                    }
+                    unsafe { wit_import1(_rt::as_i64(&timestamp), ptr0) };
+                    let l2 = i32::from(*ptr0.add(0).cast::<u8>());
+                    let result6 = match l2 {
+                        0 => {
+                            let e = ();
+                            Ok(e)
+                        }
+                        1 => {
+                            let e = {
+                                let l3 = *ptr0
+                                    .add(::core::mem::size_of::<*const u8>())
+                                    .cast::<*mut u8>();
+                                let l4 = *ptr0
+                                    .add(2 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<usize>();
+                                let len5 = l4;
+                                let bytes5 = _rt::Vec::from_raw_parts(
+                                    l3.cast(),
+                                    len5,
+                                    len5,
+                                );
+                                _rt::string_lift(bytes5)
+                            };
+                            Err(e)
+                        }
+                        _ => _rt::invalid_enum_discriminant(),
+                    };
+                    result6
+                }
+            }
+        }
         /// # WebSocket Types
         ///
         /// Types used for WebSocket communication.
@@ -9782,6 +10048,29 @@ mod _rt {
         }
     }
     pub use alloc_crate::alloc;
+    pub fn as_i64<T: AsI64>(t: T) -> i64 {
+        t.as_i64()
+    }
+    pub trait AsI64 {
+        fn as_i64(self) -> i64;
+    }
+    impl<'a, T: Copy + AsI64> AsI64 for &'a T {
+        fn as_i64(self) -> i64 {
+            (*self).as_i64()
+        }
+    }
+    impl AsI64 for i64 {
+        #[inline]
+        fn as_i64(self) -> i64 {
+            self as i64
+        }
+    }
+    impl AsI64 for u64 {
+        #[inline]
+        fn as_i64(self) -> i64 {
+            self as i64
+        }
+    }
     pub fn as_i32<T: AsI32>(t: T) -> i32 {
         t.as_i32()
     }
@@ -9841,29 +10130,6 @@ mod _rt {
             self as i32
         }
     }
-    pub fn as_i64<T: AsI64>(t: T) -> i64 {
-        t.as_i64()
-    }
-    pub trait AsI64 {
-        fn as_i64(self) -> i64;
-    }
-    impl<'a, T: Copy + AsI64> AsI64 for &'a T {
-        fn as_i64(self) -> i64 {
-            (*self).as_i64()
-        }
-    }
-    impl AsI64 for i64 {
-        #[inline]
-        fn as_i64(self) -> i64 {
-            self as i64
-        }
-    }
-    impl AsI64 for u64 {
-        #[inline]
-        fn as_i64(self) -> i64 {
-            self as i64
-        }
-    }
     #[cfg(target_arch = "wasm32")]
     pub fn run_ctors_once() {
         wit_bindgen_rt::run_ctors_once();
@@ -9912,8 +10178,8 @@ pub(crate) use __export_single_chat_impl as export;
 )]
 #[doc(hidden)]
 #[allow(clippy::octal_escapes)]
-pub static __WIT_BINDGEN_COMPONENT_TYPE: [u8; 5125] = *b"\
-\0asm\x0d\0\x01\0\0\x19\x16wit-component-encoding\x04\0\x07\x83'\x01A\x02\x01A)\x01\
+pub static __WIT_BINDGEN_COMPONENT_TYPE: [u8; 5224] = *b"\
+\0asm\x0d\0\x01\0\0\x19\x16wit-component-encoding\x04\0\x07\xe6'\x01A\x02\x01A+\x01\
 B\x14\x01p}\x04\0\x04json\x03\0\0\x01p}\x01k\x02\x04\0\x05state\x03\0\x03\x01s\x04\
 \0\x08actor-id\x03\0\x05\x01s\x04\0\x0achannel-id\x03\0\x07\x01k\x01\x01r\x02\x08\
 accepted\x7f\x07message\x09\x04\0\x0echannel-accept\x03\0\x0a\x01kw\x01r\x03\x0a\
@@ -9978,62 +10244,64 @@ eater/http-types\x05\x0a\x02\x03\0\x06\x0chttp-request\x02\x03\0\x06\x0dhttp-res
 ponse\x01B\x07\x02\x03\x02\x01\x0b\x04\0\x0chttp-request\x03\0\0\x02\x03\x02\x01\
 \x0c\x04\0\x0dhttp-response\x03\0\x02\x01j\x01\x03\x01s\x01@\x01\x03req\x01\0\x04\
 \x04\0\x09send-http\x01\x05\x03\0\x18ntwk:theater/http-client\x05\x0d\x01B\x07\x01\
-q\x07\x04text\0\0\x06binary\0\0\x07connect\0\0\x05close\0\0\x04ping\0\0\x04pong\0\
-\0\x05other\x01s\0\x04\0\x0cmessage-type\x03\0\0\x01p}\x01k\x02\x01ks\x01r\x03\x02\
-ty\x01\x04data\x03\x04text\x04\x04\0\x11websocket-message\x03\0\x05\x03\0\x1cntw\
-k:theater/websocket-types\x05\x0e\x02\x03\0\0\x05state\x02\x03\0\x06\x0dserver-c\
-onfig\x02\x03\0\x06\x0bserver-info\x02\x03\0\x06\x0atls-config\x02\x03\0\x08\x11\
-websocket-message\x01B9\x02\x03\x02\x01\x0f\x04\0\x05state\x03\0\0\x02\x03\x02\x01\
-\x0b\x04\0\x0chttp-request\x03\0\x02\x02\x03\x02\x01\x0c\x04\0\x0dhttp-response\x03\
-\0\x04\x02\x03\x02\x01\x10\x04\0\x0dserver-config\x03\0\x06\x02\x03\x02\x01\x11\x04\
-\0\x0bserver-info\x03\0\x08\x02\x03\x02\x01\x12\x04\0\x0atls-config\x03\0\x0a\x02\
-\x03\x02\x01\x13\x04\0\x11websocket-message\x03\0\x0c\x01w\x04\0\x09server-id\x03\
-\0\x0e\x01w\x04\0\x0ahandler-id\x03\0\x10\x01w\x04\0\x08route-id\x03\0\x12\x01w\x04\
-\0\x0dmiddleware-id\x03\0\x14\x01j\x01\x0f\x01s\x01@\x01\x06config\x07\0\x16\x04\
-\0\x0dcreate-server\x01\x17\x01j\x01\x09\x01s\x01@\x01\x09server-id\x0f\0\x18\x04\
-\0\x0fget-server-info\x01\x19\x01j\x01{\x01s\x01@\x01\x09server-id\x0f\0\x1a\x04\
-\0\x0cstart-server\x01\x1b\x01j\0\x01s\x01@\x01\x09server-id\x0f\0\x1c\x04\0\x0b\
-stop-server\x01\x1d\x04\0\x0edestroy-server\x01\x1d\x01j\x01\x11\x01s\x01@\x01\x0c\
-handler-names\0\x1e\x04\0\x10register-handler\x01\x1f\x01j\x01\x13\x01s\x01@\x04\
-\x09server-id\x0f\x04paths\x06methods\x0ahandler-id\x11\0\x20\x04\0\x09add-route\
-\x01!\x01@\x01\x08route-id\x13\0\x1c\x04\0\x0cremove-route\x01\"\x01j\x01\x15\x01\
-s\x01@\x03\x09server-id\x0f\x04paths\x0ahandler-id\x11\0#\x04\0\x0eadd-middlewar\
-e\x01$\x01@\x01\x0dmiddleware-id\x15\0\x1c\x04\0\x11remove-middleware\x01%\x01k\x11\
-\x01@\x05\x09server-id\x0f\x04paths\x12connect-handler-id&\x12message-handler-id\
-\x11\x15disconnect-handler-id&\0\x1c\x04\0\x10enable-websocket\x01'\x01@\x02\x09\
-server-id\x0f\x04paths\0\x1c\x04\0\x11disable-websocket\x01(\x01@\x03\x09server-\
-id\x0f\x0dconnection-idw\x07message\x0d\0\x1c\x04\0\x16send-websocket-message\x01\
-)\x01@\x02\x09server-id\x0f\x0dconnection-idw\0\x1c\x04\0\x0fclose-websocket\x01\
-*\x03\0\x1bntwk:theater/http-framework\x05\x14\x02\x03\0\0\x05event\x02\x03\0\0\x0e\
-channel-accept\x01B\x1e\x02\x03\x02\x01\x01\x04\0\x04json\x03\0\0\x02\x03\x02\x01\
-\x15\x04\0\x05event\x03\0\x02\x02\x03\x02\x01\x06\x04\0\x0achannel-id\x03\0\x04\x02\
-\x03\x02\x01\x16\x04\0\x0echannel-accept\x03\0\x06\x01k\x01\x01o\x01\x01\x01o\x01\
-\x08\x01j\x01\x0a\x01s\x01@\x02\x05state\x08\x06params\x09\0\x0b\x04\0\x0bhandle\
--send\x01\x0c\x01o\x02s\x01\x01o\x02\x08\x0a\x01j\x01\x0e\x01s\x01@\x02\x05state\
-\x08\x06params\x0d\0\x0f\x04\0\x0ehandle-request\x01\x10\x01o\x01\x07\x01o\x02\x08\
-\x11\x01j\x01\x12\x01s\x01@\x02\x05state\x08\x06params\x09\0\x13\x04\0\x13handle\
--channel-open\x01\x14\x01o\x02\x05\x01\x01@\x02\x05state\x08\x06params\x15\0\x0b\
-\x04\0\x16handle-channel-message\x01\x16\x01o\x01\x05\x01@\x02\x05state\x08\x06p\
-arams\x17\0\x0b\x04\0\x14handle-channel-close\x01\x18\x04\0\"ntwk:theater/messag\
-e-server-client\x05\x17\x01B\x07\x02\x03\x02\x01\x0f\x04\0\x05state\x03\0\0\x01o\
-\x01s\x01o\x01\x01\x01j\x01\x03\x01s\x01@\x02\x05state\x01\x06params\x02\0\x04\x04\
-\0\x04init\x01\x05\x04\0\x12ntwk:theater/actor\x05\x18\x02\x03\0\x06\x11middlewa\
-re-result\x02\x03\0\x09\x0ahandler-id\x01B'\x02\x03\x02\x01\x0f\x04\0\x05state\x03\
-\0\0\x02\x03\x02\x01\x0b\x04\0\x0chttp-request\x03\0\x02\x02\x03\x02\x01\x0c\x04\
-\0\x0dhttp-response\x03\0\x04\x02\x03\x02\x01\x13\x04\0\x11websocket-message\x03\
-\0\x06\x02\x03\x02\x01\x19\x04\0\x11middleware-result\x03\0\x08\x02\x03\x02\x01\x1a\
-\x04\0\x0ahandler-id\x03\0\x0a\x01o\x02\x0b\x03\x01o\x01\x05\x01o\x02\x01\x0d\x01\
-j\x01\x0e\x01s\x01@\x02\x05state\x01\x06params\x0c\0\x0f\x04\0\x0ehandle-request\
-\x01\x10\x01o\x01\x09\x01o\x02\x01\x11\x01j\x01\x12\x01s\x01@\x02\x05state\x01\x06\
-params\x0c\0\x13\x04\0\x11handle-middleware\x01\x14\x01ks\x01o\x04\x0bws\x15\x01\
-o\x01\x01\x01j\x01\x17\x01s\x01@\x02\x05state\x01\x06params\x16\0\x18\x04\0\x18h\
-andle-websocket-connect\x01\x19\x01o\x03\x0bw\x07\x01p\x07\x01o\x01\x1b\x01o\x02\
-\x01\x1c\x01j\x01\x1d\x01s\x01@\x02\x05state\x01\x06params\x1a\0\x1e\x04\0\x18ha\
-ndle-websocket-message\x01\x1f\x01o\x02\x0bw\x01@\x02\x05state\x01\x06params\x20\
-\0\x18\x04\0\x1bhandle-websocket-disconnect\x01!\x04\0\x1antwk:theater/http-hand\
-lers\x05\x1b\x04\0\x18ntwk:theater/single-chat\x04\0\x0b\x11\x01\0\x0bsingle-cha\
-t\x03\0\0\0G\x09producers\x01\x0cprocessed-by\x02\x0dwit-component\x070.227.1\x10\
-wit-bindgen-rust\x060.41.0";
+@\0\0w\x04\0\x03now\x01\0\x01j\0\x01s\x01@\x01\x08durationw\0\x01\x04\0\x05sleep\
+\x01\x02\x01@\x01\x09timestampw\0\x01\x04\0\x08deadline\x01\x03\x03\0\x13ntwk:th\
+eater/timing\x05\x0e\x01B\x07\x01q\x07\x04text\0\0\x06binary\0\0\x07connect\0\0\x05\
+close\0\0\x04ping\0\0\x04pong\0\0\x05other\x01s\0\x04\0\x0cmessage-type\x03\0\0\x01\
+p}\x01k\x02\x01ks\x01r\x03\x02ty\x01\x04data\x03\x04text\x04\x04\0\x11websocket-\
+message\x03\0\x05\x03\0\x1cntwk:theater/websocket-types\x05\x0f\x02\x03\0\0\x05s\
+tate\x02\x03\0\x06\x0dserver-config\x02\x03\0\x06\x0bserver-info\x02\x03\0\x06\x0a\
+tls-config\x02\x03\0\x09\x11websocket-message\x01B9\x02\x03\x02\x01\x10\x04\0\x05\
+state\x03\0\0\x02\x03\x02\x01\x0b\x04\0\x0chttp-request\x03\0\x02\x02\x03\x02\x01\
+\x0c\x04\0\x0dhttp-response\x03\0\x04\x02\x03\x02\x01\x11\x04\0\x0dserver-config\
+\x03\0\x06\x02\x03\x02\x01\x12\x04\0\x0bserver-info\x03\0\x08\x02\x03\x02\x01\x13\
+\x04\0\x0atls-config\x03\0\x0a\x02\x03\x02\x01\x14\x04\0\x11websocket-message\x03\
+\0\x0c\x01w\x04\0\x09server-id\x03\0\x0e\x01w\x04\0\x0ahandler-id\x03\0\x10\x01w\
+\x04\0\x08route-id\x03\0\x12\x01w\x04\0\x0dmiddleware-id\x03\0\x14\x01j\x01\x0f\x01\
+s\x01@\x01\x06config\x07\0\x16\x04\0\x0dcreate-server\x01\x17\x01j\x01\x09\x01s\x01\
+@\x01\x09server-id\x0f\0\x18\x04\0\x0fget-server-info\x01\x19\x01j\x01{\x01s\x01\
+@\x01\x09server-id\x0f\0\x1a\x04\0\x0cstart-server\x01\x1b\x01j\0\x01s\x01@\x01\x09\
+server-id\x0f\0\x1c\x04\0\x0bstop-server\x01\x1d\x04\0\x0edestroy-server\x01\x1d\
+\x01j\x01\x11\x01s\x01@\x01\x0chandler-names\0\x1e\x04\0\x10register-handler\x01\
+\x1f\x01j\x01\x13\x01s\x01@\x04\x09server-id\x0f\x04paths\x06methods\x0ahandler-\
+id\x11\0\x20\x04\0\x09add-route\x01!\x01@\x01\x08route-id\x13\0\x1c\x04\0\x0crem\
+ove-route\x01\"\x01j\x01\x15\x01s\x01@\x03\x09server-id\x0f\x04paths\x0ahandler-\
+id\x11\0#\x04\0\x0eadd-middleware\x01$\x01@\x01\x0dmiddleware-id\x15\0\x1c\x04\0\
+\x11remove-middleware\x01%\x01k\x11\x01@\x05\x09server-id\x0f\x04paths\x12connec\
+t-handler-id&\x12message-handler-id\x11\x15disconnect-handler-id&\0\x1c\x04\0\x10\
+enable-websocket\x01'\x01@\x02\x09server-id\x0f\x04paths\0\x1c\x04\0\x11disable-\
+websocket\x01(\x01@\x03\x09server-id\x0f\x0dconnection-idw\x07message\x0d\0\x1c\x04\
+\0\x16send-websocket-message\x01)\x01@\x02\x09server-id\x0f\x0dconnection-idw\0\x1c\
+\x04\0\x0fclose-websocket\x01*\x03\0\x1bntwk:theater/http-framework\x05\x15\x02\x03\
+\0\0\x05event\x02\x03\0\0\x0echannel-accept\x01B\x1e\x02\x03\x02\x01\x01\x04\0\x04\
+json\x03\0\0\x02\x03\x02\x01\x16\x04\0\x05event\x03\0\x02\x02\x03\x02\x01\x06\x04\
+\0\x0achannel-id\x03\0\x04\x02\x03\x02\x01\x17\x04\0\x0echannel-accept\x03\0\x06\
+\x01k\x01\x01o\x01\x01\x01o\x01\x08\x01j\x01\x0a\x01s\x01@\x02\x05state\x08\x06p\
+arams\x09\0\x0b\x04\0\x0bhandle-send\x01\x0c\x01o\x02s\x01\x01o\x02\x08\x0a\x01j\
+\x01\x0e\x01s\x01@\x02\x05state\x08\x06params\x0d\0\x0f\x04\0\x0ehandle-request\x01\
+\x10\x01o\x01\x07\x01o\x02\x08\x11\x01j\x01\x12\x01s\x01@\x02\x05state\x08\x06pa\
+rams\x09\0\x13\x04\0\x13handle-channel-open\x01\x14\x01o\x02\x05\x01\x01@\x02\x05\
+state\x08\x06params\x15\0\x0b\x04\0\x16handle-channel-message\x01\x16\x01o\x01\x05\
+\x01@\x02\x05state\x08\x06params\x17\0\x0b\x04\0\x14handle-channel-close\x01\x18\
+\x04\0\"ntwk:theater/message-server-client\x05\x18\x01B\x07\x02\x03\x02\x01\x10\x04\
+\0\x05state\x03\0\0\x01o\x01s\x01o\x01\x01\x01j\x01\x03\x01s\x01@\x02\x05state\x01\
+\x06params\x02\0\x04\x04\0\x04init\x01\x05\x04\0\x12ntwk:theater/actor\x05\x19\x02\
+\x03\0\x06\x11middleware-result\x02\x03\0\x0a\x0ahandler-id\x01B'\x02\x03\x02\x01\
+\x10\x04\0\x05state\x03\0\0\x02\x03\x02\x01\x0b\x04\0\x0chttp-request\x03\0\x02\x02\
+\x03\x02\x01\x0c\x04\0\x0dhttp-response\x03\0\x04\x02\x03\x02\x01\x14\x04\0\x11w\
+ebsocket-message\x03\0\x06\x02\x03\x02\x01\x1a\x04\0\x11middleware-result\x03\0\x08\
+\x02\x03\x02\x01\x1b\x04\0\x0ahandler-id\x03\0\x0a\x01o\x02\x0b\x03\x01o\x01\x05\
+\x01o\x02\x01\x0d\x01j\x01\x0e\x01s\x01@\x02\x05state\x01\x06params\x0c\0\x0f\x04\
+\0\x0ehandle-request\x01\x10\x01o\x01\x09\x01o\x02\x01\x11\x01j\x01\x12\x01s\x01\
+@\x02\x05state\x01\x06params\x0c\0\x13\x04\0\x11handle-middleware\x01\x14\x01ks\x01\
+o\x04\x0bws\x15\x01o\x01\x01\x01j\x01\x17\x01s\x01@\x02\x05state\x01\x06params\x16\
+\0\x18\x04\0\x18handle-websocket-connect\x01\x19\x01o\x03\x0bw\x07\x01p\x07\x01o\
+\x01\x1b\x01o\x02\x01\x1c\x01j\x01\x1d\x01s\x01@\x02\x05state\x01\x06params\x1a\0\
+\x1e\x04\0\x18handle-websocket-message\x01\x1f\x01o\x02\x0bw\x01@\x02\x05state\x01\
+\x06params\x20\0\x18\x04\0\x1bhandle-websocket-disconnect\x01!\x04\0\x1antwk:the\
+ater/http-handlers\x05\x1c\x04\0\x18ntwk:theater/single-chat\x04\0\x0b\x11\x01\0\
+\x0bsingle-chat\x03\0\0\0G\x09producers\x01\x0cprocessed-by\x02\x0dwit-component\
+\x070.227.1\x10wit-bindgen-rust\x060.41.0";
 #[inline(never)]
 #[doc(hidden)]
 pub fn __link_custom_section_describing_imports() {
@@ -0,0 +1,199 @@
+pub mod summary;
+
+use crate::messages::tokens::estimate_message_tokens;
+use crate::messages::{GenerationParams, Message, ModelInfo, SystemMessage};
+use serde::{Deserialize, Serialize};
+
+// The recap of dropped turns may use up to 1/SUMMARY_SHARE of the prompt budget
+const SUMMARY_SHARE: u32 = 4;
+
+// How much of each dropped turn is quoted in the recap
+const SUMMARY_SNIPPET_CHARS: usize = 200;
+
+const SUMMARY_HEADER: &str =
+    "Earlier turns of this conversation were left out to fit the context window. A condensed recap:";
+
+/// What to do when a conversation no longer fits the model's context window
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ContextStrategy {
+    /// Drop the oldest turns until the rest fits
+    #[default]
+    DropOldest,
+    /// Drop the oldest turns, but always keep the chat's pinned messages
+    KeepPinned,
+    /// Drop the oldest turns and send a condensed recap of them instead
+    Summarize,
+}
+
+/// One message of the conversation and the chain entries it was built from
+#[derive(Debug, Clone)]
+pub struct ContextItem {
+    pub entry_ids: Vec<String>,
+    pub message: Message,
+    pub pinned: bool,
+    pub summary: bool, // A stored summary standing in for the entries it covers
+}
+
+/// The messages sent for a request, and which chain entries made it in
+#[derive(Serialize, Debug, Clone)]
+pub struct ContextWindow {
+    #[serde(skip)]
+    pub messages: Vec<Message>,
+    pub strategy: ContextStrategy,
+    pub token_limit: Option<u32>, // None when the model is unknown and nothing was trimmed
+    pub estimated_tokens: u32,
+    pub included: Vec<String>,
+    pub omitted: Vec<String>,
+    pub summarized: bool, // A recap of the omitted entries was sent in their place
+}
+
+/// Tokens available for the prompt once room for the reply is set aside. The reply gets
+/// its `max_tokens`, but never more than half of the window.
+pub fn prompt_token_limit(model: &ModelInfo, params: &GenerationParams) -> u32 {
+    let window = model.context_window.unwrap_or(model.max_tokens);
+    let reply = params
+        .max_tokens
+        .unwrap_or(model.max_tokens)
+        .min(window / 2);
+    window - reply
+}
+
+/// Fit the conversation into `token_limit` using `strategy`.
+///
+/// The system prompt and the latest turn are always sent. Older turns are added newest
+/// first while they fit, and the kept run of recent turns always starts with a user turn
+/// (or a stored summary) so that no tool result or reply is sent without what it answers.
+pub fn build(
+    system: Option<Message>,
+    items: Vec<ContextItem>,
+    token_limit: Option<u32>,
+    strategy: ContextStrategy,
+) -> ContextWindow {
+    let system_tokens = system.as_ref().map_or(0, estimate_message_tokens);
+    let item_tokens: Vec<u32> = items
+        .iter()
+        .map(|item| estimate_message_tokens(&item.message))
+        .collect();
+    let total_tokens = system_tokens + item_tokens.iter().sum::<u32>();
+
+    let mut keep = vec![true; items.len()];
+    let mut summary = None;
+
+    if let Some(limit) = token_limit.filter(|limit| total_tokens > *limit) {
+        keep = vec![false; items.len()];
+        let mut budget = limit.saturating_sub(system_tokens);
+
+        let summary_budget = if strategy == ContextStrategy::Summarize {
+            budget / SUMMARY_SHARE
+        } else {
+            0
+        };
+        budget -= summary_budget;
+
+        let mut pinned = vec![false; items.len()];
+        if strategy == ContextStrategy::KeepPinned {
+            for (i, item) in items.iter().enumerate() {
+                if item.pinned && item_tokens[i] <= budget {
+                    pinned[i] = true;
+                    budget -= item_tokens[i];
+                }
+            }
+        }
+
+        let last = items.len().saturating_sub(1);
+        let mut first_recent = items.len();
+        for i in (0..items.len()).rev() {
+            if !pinned[i] {
+                if item_tokens[i] > budget && i != last {
+                    break;
+                }
+                budget = budget.saturating_sub(item_tokens[i]);
+            }
+            first_recent = i;
+        }
+        while first_recent < last
+            && !matches!(
+                items[first_recent].message,
+                Message::User(_) | Message::System(_)
+            )
+        {
+            first_recent += 1;
+        }
+
+        for (i, kept) in keep.iter_mut().enumerate() {
+            *kept = pinned[i] || i >= first_recent;
+        }
+
+        if summary_budget > 0 {
+            summary = recap(&items, &keep, summary_budget);
+        }
+    }
+
+    let mut messages = Vec::new();
+    messages.extend(system);
+    let summarized = summary.is_some();
+    messages.extend(summary);
+
+    let mut included = Vec::new();
+    let mut omitted = Vec::new();
+    for (item, kept) in items.into_iter().zip(keep) {
+        if kept {
+            included.extend(item.entry_ids);
+            messages.push(item.message);
+        } else {
+            omitted.extend(item.entry_ids);
+        }
+    }
+
+    ContextWindow {
+        estimated_tokens: messages.iter().map(estimate_message_tokens).sum(),
+        messages,
+        strategy,
+        token_limit,
+        included,
+        omitted,
+        summarized,
+    }
+}
+
+// Quote the start of each omitted turn, keeping the most recent ones that fit in `budget`
+fn recap(items: &[ContextItem], keep: &[bool], budget: u32) -> Option<Message> {
+    let mut lines = Vec::new();
+    let mut recap = SystemMessage {
+        content: SUMMARY_HEADER.to_string(),
+    };
+
+    for (item, _) in items.iter().zip(keep).rev().filter(|(_, kept)| !**kept) {
+        let (role, content) = match &item.message {
+            Message::User(msg) => ("User", &msg.content),
+            Message::Assistant(msg) if !msg.content.is_empty() => ("Assistant", &msg.content),
+            _ => continue,
+        };
+        let mut snippet: String = content.chars().take(SUMMARY_SNIPPET_CHARS).collect();
+        if snippet.len() < content.len() {
+            snippet.push('…');
+        }
+        lines.push(format!("{}: {}", role, snippet));
+
+        let content = format!(
+            "{}\n\n{}",
+            SUMMARY_HEADER,
+            lines.iter().rev().cloned().collect::<Vec<_>>().join("\n")
+        );
+        if estimate_message_tokens(&Message::System(SystemMessage {
+            content: content.clone(),
+        })) > budget
+        {
+            lines.pop();
+            break;
+        }
+        recap.content = content;
+    }
+
+    if lines.is_empty() {
+        None
+    } else {
+        Some(Message::System(recap))
+    }
+}
@@ -0,0 +1,81 @@
+use crate::context::ContextItem;
+use crate::messages::{Message, SystemMessage, UserMessage};
+use serde::{Deserialize, Serialize};
+
+// Turns at the end of the chat that are left out of a summary unless configured otherwise
+const DEFAULT_KEEP_RECENT: usize = 4;
+
+const SUMMARY_INSTRUCTIONS: &str = "You summarize conversations between a user and an AI assistant. \
+Write a concise summary of the transcript you are given that keeps every fact, decision, open question \
+and piece of code the conversation may need later. Reply with the summary only.";
+
+// Put in front of a summary when it is sent in place of the turns it covers
+const SUMMARY_PREFIX: &str = "Summary of the earlier conversation:\n\n";
+
+/// Condensing older history into a summary with a (cheap) model
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SummarizationConfig {
+    pub model_id: String,
+    pub threshold_tokens: Option<u32>, // Summarize automatically past this size; on demand only when unset
+    #[serde(default = "default_keep_recent")]
+    pub keep_recent: usize, // Turns at the end that are always sent verbatim
+}
+
+fn default_keep_recent() -> usize {
+    DEFAULT_KEEP_RECENT
+}
+
+/// Where a summary of `items` should end so the last `keep_recent` turns stay verbatim.
+/// The kept turns start with a user turn, and `None` means there is nothing new to summarize.
+pub fn span_end(items: &[ContextItem], keep_recent: usize) -> Option<usize> {
+    let end = (0..=items.len().saturating_sub(keep_recent))
+        .rev()
+        .find(|i| {
+            *i < items.len() && !items[*i].summary && matches!(items[*i].message, Message::User(_))
+        })?;
+
+    // A span made only of earlier summaries would just be summarized again
+    let has_new_turns = items[..end].iter().any(|item| !item.summary);
+    if has_new_turns {
+        Some(end)
+    } else {
+        None
+    }
+}
+
+/// The request sent to the summarization model for a span of the conversation
+pub fn request_messages(span: &[ContextItem]) -> Vec<Message> {
+    let transcript = span
+        .iter()
+        .filter_map(|item| match &item.message {
+            Message::User(msg) if item.summary => {
+                Some(format!("[Earlier summary]\n{}", msg.content))
+            }
+            Message::User(msg) => Some(format!("User: {}", msg.content)),
+            Message::Assistant(msg) if !msg.content.is_empty() => {
+                Some(format!("Assistant: {}", msg.content))
+            }
+            Message::Tool(msg) => Some(format!("Tool result: {}", msg.content)),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    vec![
+        Message::System(SystemMessage {
+            content: SUMMARY_INSTRUCTIONS.to_string(),
+        }),
+        Message::User(UserMessage {
+            content: transcript,
+        }),
+    ]
+}
+
+/// The message a stored summary is sent as. It goes out as a user turn so that it keeps its
+/// place in the conversation; providers such as Anthropic would otherwise fold it into the
+/// system prompt.
+pub fn summary_message(text: &str) -> Message {
+    Message::User(UserMessage {
+        content: format!("{}{}", SUMMARY_PREFIX, text),
+    })
+}
@@ -0,0 +1,275 @@
+use crate::messages::{ChainEntry, ChatInfo, Message, MessageData, ToolCall};
+use crate::usage::{day_key, message_usage};
+use serde::Serialize;
+use serde_json::json;
+
+/// Formats a chat can be exported in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Markdown,
+    Json,
+    Html,
+}
+
+impl ExportFormat {
+    pub fn parse(format: &str) -> Option<Self> {
+        match format.to_lowercase().as_str() {
+            "markdown" | "md" => Some(ExportFormat::Markdown),
+            "json" => Some(ExportFormat::Json),
+            "html" => Some(ExportFormat::Html),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "text/markdown; charset=utf-8",
+            ExportFormat::Json => "application/json",
+            ExportFormat::Html => "text/html; charset=utf-8",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Json => "json",
+            ExportFormat::Html => "html",
+        }
+    }
+}
+
+/// One turn of an exported conversation. Continued replies are joined into a single turn.
+#[derive(Serialize, Debug, Clone)]
+pub struct ExportedMessage {
+    pub id: Option<String>,
+    pub parents: Vec<String>,
+    pub role: &'static str,
+    pub content: String,
+    pub model: Option<String>,
+    pub timestamp: Option<u64>, // Milliseconds since the epoch
+    pub cost: Option<f64>,      // USD
+    pub tool_calls: Vec<ToolCall>,
+    pub error: Option<String>,
+}
+
+/// Render a chat's chain, oldest entry first
+pub fn render(chat_info: &ChatInfo, chain: &[ChainEntry], format: ExportFormat) -> String {
+    let messages = exported_messages(chain);
+    match format {
+        ExportFormat::Markdown => render_markdown(chat_info, &messages),
+        ExportFormat::Json => serde_json::to_string_pretty(&json!({
+            "chat": chat_info,
+            "total_cost": total_cost(&messages),
+            "messages": messages,
+        }))
+        .unwrap(),
+        ExportFormat::Html => render_html(chat_info, &messages),
+    }
+}
+
+/// A file name for the export, derived from the chat's name
+pub fn file_name(chat_info: &ChatInfo, format: ExportFormat) -> String {
+    let name: String = chat_info
+        .name
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect();
+    let name = name.trim_matches('-');
+    let name = if name.is_empty() { "chat" } else { name };
+    format!("{}.{}", name, format.extension())
+}
+
+fn exported_messages(chain: &[ChainEntry]) -> Vec<ExportedMessage> {
+    let mut messages: Vec<ExportedMessage> = Vec::new();
+
+    for entry in chain {
+        let mut message = ExportedMessage {
+            id: entry.id.clone(),
+            parents: entry.parents.clone(),
+            role: "user",
+            content: String::new(),
+            model: None,
+            timestamp: entry.timestamp,
+            cost: None,
+            tool_calls: Vec::new(),
+            error: None,
+        };
+
+        match &entry.data {
+            MessageData::Chat(Message::System(msg)) => {
+                message.role = "system";
+                message.content = msg.content.clone();
+            }
+            MessageData::Chat(Message::User(msg)) => message.content = msg.content.clone(),
+            MessageData::Chat(Message::Assistant(msg)) => {
+                let cost = message_usage(msg).cost;
+
+                // Pieces of a continued reply read as one answer
+                if msg.continuation_of.is_some() && msg.error.is_none() {
+                    if let Some(previous) = messages.last_mut().filter(|m| m.role == "assistant") {
+                        previous.content.push_str(&msg.content);
+                        previous.cost = Some(previous.cost.unwrap_or(0.0) + cost);
+                        previous.tool_calls = msg.tool_calls.clone().unwrap_or_default();
+                        continue;
+                    }
+                }
+
+                message.role = "assistant";
+                message.content = msg.content.clone();
+                message.model = Some(msg.model.clone());
+                message.cost = Some(cost);
+                message.tool_calls = msg.tool_calls.clone().unwrap_or_default();
+                message.error = msg.error.as_ref().map(|error| error.to_string());
+            }
+            MessageData::Chat(Message::Tool(msg)) => {
+                message.role = "tool";
+                message.content = msg.content.clone();
+            }
+            MessageData::Summary {
+                covers,
+                text,
+                model,
+            } => {
+                message.role = "summary";
+                message.content =
+                    format!("Summary of {} earlier messages:\n\n{}", covers.len(), text);
+                message.model = Some(model.clone());
+            }
+        }
+
+        messages.push(message);
+    }
+
+    messages
+}
+
+fn total_cost(messages: &[ExportedMessage]) -> f64 {
+    messages.iter().filter_map(|message| message.cost).sum()
+}
+
+// Heading details for a turn: role, model, time and cost
+fn heading(message: &ExportedMessage) -> String {
+    let mut parts = vec![match message.role {
+        "user" => "User".to_string(),
+        "assistant" => "Assistant".to_string(),
+        "tool" => "Tool result".to_string(),
+        "summary" => "Summary".to_string(),
+        role => role.to_string(),
+    }];
+    if let Some(model) = &message.model {
+        parts.push(model.clone());
+    }
+    if let Some(timestamp) = message.timestamp {
+        parts.push(format_timestamp(timestamp));
+    }
+    if let Some(cost) = message.cost.filter(|cost| *cost > 0.0) {
+        parts.push(format!("${:.4}", cost));
+    }
+    parts.join(" · ")
+}
+
+// UTC date and time, to the minute
+fn format_timestamp(timestamp_ms: u64) -> String {
+    let minutes = timestamp_ms / 60_000;
+    format!(
+        "{} {:02}:{:02} UTC",
+        day_key(timestamp_ms),
+        (minutes / 60) % 24,
+        minutes % 60
+    )
+}
+
+fn render_markdown(chat_info: &ChatInfo, messages: &[ExportedMessage]) -> String {
+    let mut out = format!("# {}\n\n", chat_info.name);
+    if let Some(system_prompt) = &chat_info.system_prompt {
+        out.push_str(&format!(
+            "> **System prompt:** {}\n\n",
+            system_prompt.replace('\n', "\n> ")
+        ));
+    }
+
+    for message in messages {
+        out.push_str(&format!("## {}\n\n", heading(message)));
+        if let Some(error) = &message.error {
+            out.push_str(&format!("> **Error:** {}\n\n", error));
+        }
+        if message.role == "tool" {
+            out.push_str(&format!("```\n{}\n```\n\n", message.content));
+        } else if !message.content.is_empty() {
+            out.push_str(&format!("{}\n\n", message.content));
+        }
+        for call in &message.tool_calls {
+            out.push_str(&format!(
+                "Called `{}` with:\n\n```json\n{}\n```\n\n",
+                call.name, call.arguments
+            ));
+        }
+    }
+
+    out.push_str(&format!(
+        "---\n\nTotal cost: ${:.4}\n",
+        total_cost(messages)
+    ));
+    out
+}
+
+fn render_html(chat_info: &ChatInfo, messages: &[ExportedMessage]) -> String {
+    let mut body = format!("<h1>{}</h1>\n", escape_html(&chat_info.name));
+    if let Some(system_prompt) = &chat_info.system_prompt {
+        body.push_str(&format!(
+            "<div class=\"system\"><strong>System prompt:</strong><pre>{}</pre></div>\n",
+            escape_html(system_prompt)
+        ));
+    }
+
+    for message in messages {
+        body.push_str(&format!(
+            "<section class=\"{}\">\n<h2>{}</h2>\n",
+            message.role,
+            escape_html(&heading(message))
+        ));
+        if let Some(error) = &message.error {
+            body.push_str(&format!(
+                "<p class=\"error\">Error: {}</p>\n",
+                escape_html(error)
+            ));
+        }
+        if !message.content.is_empty() {
+            body.push_str(&format!("<pre>{}</pre>\n", escape_html(&message.content)));
+        }
+        for call in &message.tool_calls {
+            body.push_str(&format!(
+                "<p>Called <code>{}</code> with:</p>\n<pre>{}</pre>\n",
+                escape_html(&call.name),
+                escape_html(&call.arguments)
+            ));
+        }
+        body.push_str("</section>\n");
+    }
+
+    body.push_str(&format!(
+        "<footer>Total cost: ${:.4}</footer>\n",
+        total_cost(messages)
+    ));
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape_html(&chat_info.name),
+        HTML_STYLE,
+        body
+    )
+}
+
+const HTML_STYLE: &str =
+    "body{font-family:sans-serif;max-width:50rem;margin:2rem auto;padding:0 1rem;}\
+section{border-left:3px solid #ccc;margin:1.5rem 0;padding-left:1rem;}\
+section.user{border-color:#4a7bd0;}section.assistant{border-color:#3ba55c;}\
+h2{font-size:.9rem;color:#555;}pre{white-space:pre-wrap;font-family:inherit;}\
+.error{color:#b00020;}footer{color:#555;margin-top:2rem;}";
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
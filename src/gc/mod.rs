@@ -0,0 +1,245 @@
+use crate::bindings::ntwk::theater::runtime::log;
+use crate::bindings::ntwk::theater::store::{self, ContentRef};
+use crate::messages::store::{parse_children_label, parse_roots_label, MessageStore};
+use crate::messages::{ChainEntry, ChatInfo};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Outcome of a garbage collection pass. The store interface cannot delete content, so
+/// orphaned blobs are only reported; sweeping removes the orphaned labels that still
+/// point into them.
+#[derive(Serialize, Debug, Clone)]
+pub struct GcReport {
+    pub total_size_before: u64, // Bytes, from calculate-total-size
+    pub total_size_after: u64,
+    pub stored: usize,
+    pub reachable: usize,
+    pub orphaned: Vec<String>, // Hashes of content nothing reaches
+    pub orphaned_size: u64,
+    pub orphaned_labels: Vec<String>,
+    pub swept: bool,
+}
+
+/// Mark everything reachable from the store's labels and report the rest. Chats are
+/// reached through the chat list and the trash, their messages through chat heads, pins
+/// and root indexes, then `ChainEntry.parents` and the children indexes of reached
+/// messages. With `sweep`, orphaned labels are removed.
+pub fn collect(store: &MessageStore, sweep: bool) -> Result<GcReport, Box<dyn std::error::Error>> {
+    let store_id = &store.store_id;
+    let total_size_before = store::calculate_total_size(store_id)?;
+    let stored: HashSet<String> = store::list_all_content(store_id)?
+        .into_iter()
+        .map(|content_ref| content_ref.hash)
+        .collect();
+
+    let mut labels = BTreeMap::new();
+    for label in store::list_labels(store_id)? {
+        if let Some(content_ref) = store::get_by_label(store_id, &label)? {
+            labels.insert(label, content_ref.hash);
+        }
+    }
+
+    let mut chat_ids: HashSet<String> = store.list_chat_ids()?.into_iter().collect();
+    chat_ids.extend(store.list_trash()?.into_iter().map(|trashed| trashed.id));
+
+    // Children indexes of known chats (and legacy shared ones) by the message they belong to
+    let mut children_indexes: HashMap<&str, Vec<&String>> = HashMap::new();
+    for (label, hash) in &labels {
+        if let LabelKind::Children { chat_id, id } = label_kind(label) {
+            if chat_id.is_none_or(|chat_id| chat_ids.contains(chat_id)) {
+                children_indexes.entry(id).or_default().push(hash);
+            }
+        }
+    }
+
+    let mut marked: HashSet<String> = HashSet::new();
+    let mut reached_entries: HashSet<String> = HashSet::new();
+    let mut pending: Vec<String> = Vec::new();
+
+    // Chat infos and root indexes of known chats, and every other document label
+    for (label, hash) in &labels {
+        match label_kind(label) {
+            LabelKind::ChatRoots(chat_id) if chat_ids.contains(chat_id) => {
+                marked.insert(hash.clone());
+                pending.extend(read::<Vec<String>>(store_id, hash)?);
+            }
+            LabelKind::ChatInfo(chat_id) if chat_ids.contains(chat_id) => {
+                marked.insert(hash.clone());
+                let chat_info: ChatInfo = read(store_id, hash)?;
+                pending.extend(chat_info.head);
+                pending.extend(chat_info.pinned_messages);
+            }
+            // The legacy chat-head label points straight at an entry
+            LabelKind::Document => pending.push(hash.clone()),
+            _ => {}
+        }
+    }
+
+    while let Some(hash) = pending.pop() {
+        if !stored.contains(&hash) || !marked.insert(hash.clone()) {
+            continue;
+        }
+
+        let content = store::get(store_id, &ContentRef { hash: hash.clone() })?;
+        if let Ok(entry) = serde_json::from_slice::<ChainEntry>(&content) {
+            reached_entries.insert(hash.clone());
+            pending.extend(entry.parents);
+
+            // Alternative branches hang off their parent's children indexes
+            for index_hash in children_indexes.get(hash.as_str()).into_iter().flatten() {
+                marked.insert((*index_hash).clone());
+                pending.extend(read::<Vec<String>>(store_id, index_hash)?);
+            }
+        }
+    }
+
+    let orphaned_labels: Vec<String> = labels
+        .iter()
+        .filter(|(label, hash)| {
+            label_orphaned(label, stored.contains(*hash), &chat_ids, &reached_entries)
+        })
+        .map(|(label, _)| label.clone())
+        .collect();
+
+    let mut orphaned: Vec<String> = stored
+        .iter()
+        .filter(|hash| !marked.contains(*hash))
+        .cloned()
+        .collect();
+    orphaned.sort();
+    let mut orphaned_size = 0;
+    for hash in &orphaned {
+        orphaned_size += store::get(store_id, &ContentRef { hash: hash.clone() })?.len() as u64;
+    }
+
+    if sweep {
+        for label in &orphaned_labels {
+            store::remove_label(store_id, label)?;
+        }
+    }
+    let total_size_after = store::calculate_total_size(store_id)?;
+
+    log(&format!(
+        "Garbage collection: {} of {} items reachable, {} orphaned ({} bytes), {} orphaned labels{}",
+        stored.len() - orphaned.len(),
+        stored.len(),
+        orphaned.len(),
+        orphaned_size,
+        orphaned_labels.len(),
+        if sweep { " removed" } else { "" }
+    ));
+
+    Ok(GcReport {
+        total_size_before,
+        total_size_after,
+        stored: stored.len(),
+        reachable: stored.len() - orphaned.len(),
+        orphaned,
+        orphaned_size,
+        orphaned_labels,
+        swept: sweep,
+    })
+}
+
+// What a label points at, going by its name
+#[derive(Debug, PartialEq)]
+enum LabelKind<'a> {
+    ChatInfo(&'a str),
+    ChatRoots(&'a str),
+    Children {
+        chat_id: Option<&'a str>,
+        id: &'a str,
+    },
+    Document, // Anything else, such as the chat list or the chat index
+}
+
+fn label_kind(label: &str) -> LabelKind<'_> {
+    if let Some((chat_id, id)) = parse_children_label(label) {
+        LabelKind::Children { chat_id, id }
+    } else if let Some(chat_id) = parse_roots_label(label) {
+        // Checked before `chat_`, which the roots label's old name also starts with
+        LabelKind::ChatRoots(chat_id)
+    } else if let Some(chat_id) = label.strip_prefix("chat_") {
+        LabelKind::ChatInfo(chat_id)
+    } else {
+        LabelKind::Document
+    }
+}
+
+// Whether a label only points into something unreachable. Chat labels go with their chat,
+// children indexes with their message, and documents only when their content is gone.
+fn label_orphaned(
+    label: &str,
+    content_stored: bool,
+    chat_ids: &HashSet<String>,
+    reached_entries: &HashSet<String>,
+) -> bool {
+    match label_kind(label) {
+        LabelKind::Children { chat_id, id } => {
+            !reached_entries.contains(id)
+                || chat_id.is_some_and(|chat_id| !chat_ids.contains(chat_id))
+        }
+        LabelKind::ChatRoots(chat_id) | LabelKind::ChatInfo(chat_id) => !chat_ids.contains(chat_id),
+        LabelKind::Document => !content_stored,
+    }
+}
+
+fn read<T: serde::de::DeserializeOwned>(
+    store_id: &str,
+    hash: &str,
+) -> Result<T, Box<dyn std::error::Error>> {
+    let content = store::get(
+        store_id,
+        &ContentRef {
+            hash: hash.to_string(),
+        },
+    )?;
+    Ok(serde_json::from_slice(&content)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::store::CHAT_INDEX_LABEL;
+
+    #[test]
+    fn chat_index_is_kept() {
+        assert_eq!(label_kind(CHAT_INDEX_LABEL), LabelKind::Document);
+        assert!(!label_orphaned(
+            CHAT_INDEX_LABEL,
+            true,
+            &HashSet::new(),
+            &HashSet::new()
+        ));
+    }
+
+    #[test]
+    fn chat_labels_go_with_their_chat() {
+        let chat_ids: HashSet<String> = ["a1b2".to_string()].into();
+        let reached: HashSet<String> = ["f00d".to_string()].into();
+
+        assert_eq!(label_kind("chat_a1b2"), LabelKind::ChatInfo("a1b2"));
+        assert_eq!(label_kind("roots_a1b2"), LabelKind::ChatRoots("a1b2"));
+        assert_eq!(label_kind("chat_roots_a1b2"), LabelKind::ChatRoots("a1b2"));
+        assert!(!label_orphaned("chat_a1b2", true, &chat_ids, &reached));
+        assert!(label_orphaned("chat_c3d4", true, &chat_ids, &reached));
+        assert!(!label_orphaned("roots_a1b2", true, &chat_ids, &reached));
+        assert!(label_orphaned("roots_c3d4", true, &chat_ids, &reached));
+        assert!(label_orphaned("chat_roots_c3d4", true, &chat_ids, &reached));
+
+        assert!(!label_orphaned(
+            "children_a1b2_f00d",
+            true,
+            &chat_ids,
+            &reached
+        ));
+        assert!(label_orphaned(
+            "children_c3d4_f00d",
+            true,
+            &chat_ids,
+            &reached
+        ));
+        assert!(!label_orphaned("children_f00d", true, &chat_ids, &reached));
+        assert!(label_orphaned("children_beef", true, &chat_ids, &reached));
+    }
+}
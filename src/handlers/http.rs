@@ -1,8 +1,17 @@
+use crate::api::error::GenerationError;
+use crate::backup::{self, Archive};
 use crate::bindings::ntwk::theater::http_client::HttpRequest as ClientHttpRequest;
 use crate::bindings::ntwk::theater::http_client::HttpResponse as ClientHttpResponse;
 use crate::bindings::ntwk::theater::runtime::log;
+use crate::export::{self, ExportFormat};
+use crate::gc;
+use crate::import::{self, ImportFormat};
+use crate::messages::search::DEFAULT_SEARCH_LIMIT;
+use crate::messages::SystemPrompt;
 use crate::resources::get_resource;
 use crate::state::State;
+use crate::usage::budget::Budgets;
+use crate::usage::UsageLedger;
 use serde_json::{json, Value};
 
 pub fn handle_request(
@@ -25,8 +34,21 @@ pub fn handle_request(
         "/styles.css" => serve_file("styles.css", "text/css", &mut state),
         "/chat.js" => serve_file("dist/chat.js", "application/javascript", &mut state),
         "/api/messages" => handle_messages_api(&req, &mut state),
+        uri if uri.starts_with("/api/messages/") => handle_message_detail_api(&req, &mut state),
         "/api/chats" => handle_chats_api(&req, &mut state),
         uri if uri.starts_with("/api/chats/") => handle_chat_detail_api(&req, &mut state),
+        "/api/prompts" => handle_prompts_api(&req, &mut state),
+        "/api/usage" => handle_usage_api(&req, &mut state),
+        "/api/budgets" => handle_budgets_api(&req, &mut state),
+        "/api/search" => handle_search_api(&req, &mut state),
+        "/api/import" => handle_import_api(&req, &mut state),
+        "/api/backup" => handle_backup_api(&req, &mut state),
+        "/api/restore" => handle_restore_api(&req, &mut state),
+        "/api/trash" => handle_trash_api(&req, &mut state),
+        "/api/admin/gc" => handle_gc_api(&req, &mut state),
+        "/api/admin/cache" => handle_cache_api(&req, &mut state),
+        uri if uri.starts_with("/api/trash/") => handle_trash_detail_api(&req, &mut state),
+        uri if uri.starts_with("/api/prompts/") => handle_prompt_detail_api(&req, &mut state),
         _ => not_found(),
     }
 }
@@ -141,6 +163,73 @@ fn handle_messages_api(
     }
 }
 
+fn handle_message_detail_api(
+    req: &ClientHttpRequest,
+    state: &mut State,
+) -> Result<(Option<Vec<u8>>, (ClientHttpResponse,)), String> {
+    // Paths look like /api/messages/{id}/{action}
+    let path = req.uri.split('?').next().unwrap_or("");
+    let path_parts: Vec<&str> = path.split('/').collect();
+    let message_id = path_parts
+        .get(3)
+        .ok_or_else(|| "Invalid message ID".to_string())?
+        .to_string();
+    let action = path_parts.get(4).copied().unwrap_or("");
+
+    match (req.method.as_str(), action) {
+        ("GET", "branches") => match state.list_branches(&message_id) {
+            Ok(branches) => json_response(200, &branches, state),
+            Err(e) => json_response(404, &json!({ "error": e.to_string() }), state),
+        },
+        ("POST", "edit") => {
+            let data = parse_json_body(req)?;
+            let content = data["content"]
+                .as_str()
+                .ok_or_else(|| "Missing 'content' field".to_string())?;
+
+            match state.edit_message(&message_id, content) {
+                Ok(entry) => json_response(
+                    201,
+                    &json!({
+                        "message": entry,
+                        "head": state.head,
+                    }),
+                    state,
+                ),
+                Err(e) => json_response(400, &json!({ "error": e.to_string() }), state),
+            }
+        }
+        ("POST", "regenerate") => {
+            let data = parse_json_body(req)?;
+            let model_id = data["model_id"]
+                .as_str()
+                .ok_or_else(|| "Missing 'model_id' field".to_string())?
+                .to_string();
+
+            match state.regenerate_response(&message_id, model_id) {
+                Ok(_) => json_response(201, &json!({ "head": state.head }), state),
+                Err(e) => {
+                    let error = GenerationError::from_boxed(e.as_ref());
+                    json_response(
+                        502,
+                        &json!({
+                            "error": error.to_string(),
+                            "code": error.code(),
+                            "retryable": error.retryable(),
+                        }),
+                        state,
+                    )
+                }
+            }
+        }
+        ("POST", "checkout") => match state.switch_branch(&message_id) {
+            Ok(_) => json_response(200, &json!({ "head": state.head }), state),
+            Err(e) => json_response(404, &json!({ "error": e.to_string() }), state),
+        },
+        _ => not_found(),
+    }
+}
+
 fn handle_chats_api(
     req: &ClientHttpRequest,
     state: &mut State,
@@ -148,18 +237,10 @@ fn handle_chats_api(
     match req.method.as_str() {
         "GET" => {
             // Get all chats
-            let chat_ids = state.store.list_chat_ids().map_err(|e| e.to_string())?;
-            let mut chats = Vec::new();
-
-            for chat_id in chat_ids {
-                if let Ok(Some(chat_info)) = state.store.get_chat_info(&chat_id) {
-                    chats.push(json!({
-                        "id": chat_info.id,
-                        "name": chat_info.name,
-                        "icon": chat_info.icon,
-                    }));
-                }
-            }
+            let chats = state
+                .store
+                .list_chat_summaries()
+                .map_err(|e| e.to_string())?;
 
             let response = ClientHttpResponse {
                 status: 200,
@@ -211,6 +292,12 @@ fn handle_chats_api(
                             "id": chat_info.id,
                             "name": chat_info.name,
                             "icon": chat_info.icon,
+                            "forked_from": chat_info.forked_from,
+                            "system_prompt": chat_info.system_prompt,
+                            "generation_params": chat_info.generation_params,
+                            "context_strategy": chat_info.context_strategy,
+                            "pinned_messages": chat_info.pinned_messages,
+                            "title_locked": chat_info.title_locked,
                         }
                     }))
                     .unwrap(),
@@ -237,16 +324,29 @@ fn handle_chats_api(
     }
 }
 
+// Chat ID and action of a `/api/chats/{id}[/{action}]` URI, ignoring any query string
+fn chat_detail_route(uri: &str) -> Option<(&str, Option<&str>)> {
+    let path = uri.split('?').next().unwrap_or("");
+    let mut path_parts = path.split('/').skip(3);
+    let chat_id = path_parts.next().filter(|chat_id| !chat_id.is_empty())?;
+    Some((chat_id, path_parts.next()))
+}
+
 fn handle_chat_detail_api(
     req: &ClientHttpRequest,
     state: &mut State,
 ) -> Result<(Option<Vec<u8>>, (ClientHttpResponse,)), String> {
-    // Extract chat ID from the path
-    let path_parts: Vec<&str> = req.uri.split('/').collect();
-    let chat_id = path_parts
-        .get(3)
-        .ok_or_else(|| "Invalid chat ID".to_string())?
-        .to_string();
+    let (chat_id, action) =
+        chat_detail_route(&req.uri).ok_or_else(|| "Invalid chat ID".to_string())?;
+    let chat_id = chat_id.to_string();
+
+    match action {
+        Some("fork") => return handle_fork_chat_api(req, state, &chat_id),
+        Some("usage") => return handle_chat_usage_api(req, state, &chat_id),
+        Some("summarize") => return handle_summarize_chat_api(req, state, &chat_id),
+        Some("export") => return handle_export_chat_api(req, state, &chat_id),
+        _ => {}
+    }
 
     match req.method.as_str() {
         "GET" => {
@@ -269,6 +369,12 @@ fn handle_chat_detail_api(
                             "id": chat_info.id,
                             "name": chat_info.name,
                             "icon": chat_info.icon,
+                            "forked_from": chat_info.forked_from,
+                            "system_prompt": chat_info.system_prompt,
+                            "generation_params": chat_info.generation_params,
+                            "context_strategy": chat_info.context_strategy,
+                            "pinned_messages": chat_info.pinned_messages,
+                            "title_locked": chat_info.title_locked,
                         }
                     }))
                     .unwrap(),
@@ -295,12 +401,49 @@ fn handle_chat_detail_api(
                 .ok_or_else(|| format!("Chat {} not found", chat_id))?;
 
             // Update fields
+            // A chat named by hand is never auto-titled
             if let Some(name) = data["name"].as_str() {
                 chat_info.name = name.to_string();
+                chat_info.title_locked = true;
+            }
+            if let Some(title_locked) = data["title_locked"].as_bool() {
+                chat_info.title_locked = title_locked;
             }
             if let Some(icon) = data["icon"].as_str() {
                 chat_info.icon = Some(icon.to_string());
             }
+            // A string sets the system prompt and an explicit null clears it
+            match data.get("system_prompt") {
+                Some(Value::String(prompt)) if !prompt.trim().is_empty() => {
+                    chat_info.system_prompt = Some(prompt.clone());
+                }
+                Some(Value::String(_)) | Some(Value::Null) => chat_info.system_prompt = None,
+                _ => {}
+            }
+            match data.get("generation_params") {
+                Some(Value::Null) => chat_info.generation_params = None,
+                Some(params) => {
+                    chat_info.generation_params = Some(
+                        serde_json::from_value(params.clone())
+                            .map_err(|e| format!("Invalid generation params: {}", e))?,
+                    );
+                }
+                None => {}
+            }
+            match data.get("context_strategy") {
+                Some(Value::Null) => chat_info.context_strategy = None,
+                Some(strategy) => {
+                    chat_info.context_strategy = Some(
+                        serde_json::from_value(strategy.clone())
+                            .map_err(|e| format!("Invalid context strategy: {}", e))?,
+                    );
+                }
+                None => {}
+            }
+            if let Some(pinned) = data.get("pinned_messages") {
+                chat_info.pinned_messages = serde_json::from_value(pinned.clone())
+                    .map_err(|e| format!("Invalid pinned messages: {}", e))?;
+            }
 
             // Save updated chat info
             state
@@ -320,6 +463,12 @@ fn handle_chat_detail_api(
                             "id": chat_info.id,
                             "name": chat_info.name,
                             "icon": chat_info.icon,
+                            "forked_from": chat_info.forked_from,
+                            "system_prompt": chat_info.system_prompt,
+                            "generation_params": chat_info.generation_params,
+                            "context_strategy": chat_info.context_strategy,
+                            "pinned_messages": chat_info.pinned_messages,
+                            "title_locked": chat_info.title_locked,
                         }
                     }))
                     .unwrap(),
@@ -366,6 +515,582 @@ fn handle_chat_detail_api(
     }
 }
 
+fn handle_fork_chat_api(
+    req: &ClientHttpRequest,
+    state: &mut State,
+    chat_id: &str,
+) -> Result<(Option<Vec<u8>>, (ClientHttpResponse,)), String> {
+    if req.method != "POST" {
+        return method_not_allowed("POST", state);
+    }
+
+    let data = parse_json_body(req)?;
+    let message_id = data["message_id"]
+        .as_str()
+        .ok_or_else(|| "Missing 'message_id' field".to_string())?;
+    let name = data["name"].as_str().map(String::from);
+
+    match state.fork_chat(chat_id, message_id, name) {
+        Ok(chat_info) => {
+            if let Err(e) = state.notify_chats_update() {
+                log(&format!("Failed to notify chats update: {}", e));
+            }
+
+            json_response(
+                201,
+                &json!({
+                    "chat": {
+                        "id": chat_info.id,
+                        "name": chat_info.name,
+                        "icon": chat_info.icon,
+                        "head": chat_info.head,
+                        "forked_from": chat_info.forked_from,
+                    }
+                }),
+                state,
+            )
+        }
+        Err(e) => json_response(404, &json!({ "error": e.to_string() }), state),
+    }
+}
+
+fn handle_summarize_chat_api(
+    req: &ClientHttpRequest,
+    state: &mut State,
+    chat_id: &str,
+) -> Result<(Option<Vec<u8>>, (ClientHttpResponse,)), String> {
+    if req.method != "POST" {
+        return method_not_allowed("POST", state);
+    }
+
+    match state.store.get_chat_info(chat_id) {
+        Ok(Some(_)) => {}
+        Ok(None) => return not_found(),
+        Err(e) => return Err(e.to_string()),
+    }
+
+    match state.summarize_chat_by_id(chat_id) {
+        Ok(Some(entry)) => json_response(201, &json!({ "summary": entry }), state),
+        Ok(None) => json_response(200, &json!({ "summary": null }), state),
+        Err(e) => {
+            let error = GenerationError::from_boxed(e.as_ref());
+            json_response(
+                502,
+                &json!({
+                    "error": error.to_string(),
+                    "code": error.code(),
+                    "retryable": error.retryable(),
+                }),
+                state,
+            )
+        }
+    }
+}
+
+fn handle_export_chat_api(
+    req: &ClientHttpRequest,
+    state: &mut State,
+    chat_id: &str,
+) -> Result<(Option<Vec<u8>>, (ClientHttpResponse,)), String> {
+    if req.method != "GET" {
+        return method_not_allowed("GET", state);
+    }
+
+    let format = query_param(&req.uri, "format").unwrap_or_else(|| "markdown".to_string());
+    let format = match ExportFormat::parse(&format) {
+        Some(format) => format,
+        None => {
+            return json_response(
+                400,
+                &json!({ "error": format!("Unknown export format: {}", format) }),
+                state,
+            )
+        }
+    };
+
+    let chat_info = match state.store.get_chat_info(chat_id) {
+        Ok(Some(chat_info)) => chat_info,
+        Ok(None) => return not_found(),
+        Err(e) => return Err(e.to_string()),
+    };
+    let chain = state.get_chat_chain(chat_id).map_err(|e| e.to_string())?;
+
+    let response = ClientHttpResponse {
+        status: 200,
+        headers: vec![
+            (
+                "Content-Type".to_string(),
+                format.content_type().to_string(),
+            ),
+            (
+                "Content-Disposition".to_string(),
+                format!(
+                    "attachment; filename=\"{}\"",
+                    export::file_name(&chat_info, format)
+                ),
+            ),
+        ],
+        body: Some(export::render(&chat_info, &chain, format).into_bytes()),
+    };
+    Ok((Some(serde_json::to_vec(state).unwrap()), (response,)))
+}
+
+fn handle_prompts_api(
+    req: &ClientHttpRequest,
+    state: &mut State,
+) -> Result<(Option<Vec<u8>>, (ClientHttpResponse,)), String> {
+    match req.method.as_str() {
+        "GET" => {
+            let prompts = state
+                .store
+                .list_system_prompts()
+                .map_err(|e| e.to_string())?;
+            json_response(200, &json!({ "prompts": prompts }), state)
+        }
+        "POST" => {
+            let data = parse_json_body(req)?;
+            let name = data["name"]
+                .as_str()
+                .ok_or_else(|| "Missing 'name' field".to_string())?;
+            let content = data["content"]
+                .as_str()
+                .ok_or_else(|| "Missing 'content' field".to_string())?;
+
+            let prompt = SystemPrompt {
+                name: name.to_string(),
+                content: content.to_string(),
+            };
+            state
+                .store
+                .save_system_prompt(prompt.clone())
+                .map_err(|e| e.to_string())?;
+
+            json_response(201, &json!({ "prompt": prompt }), state)
+        }
+        _ => {
+            let response = ClientHttpResponse {
+                status: 405,
+                headers: vec![
+                    ("Content-Type".to_string(), "application/json".to_string()),
+                    ("Allow".to_string(), "GET, POST".to_string()),
+                ],
+                body: Some(
+                    serde_json::to_vec(&json!({
+                        "error": "Method not allowed"
+                    }))
+                    .unwrap(),
+                ),
+            };
+            Ok((Some(serde_json::to_vec(state).unwrap()), (response,)))
+        }
+    }
+}
+
+fn handle_prompt_detail_api(
+    req: &ClientHttpRequest,
+    state: &mut State,
+) -> Result<(Option<Vec<u8>>, (ClientHttpResponse,)), String> {
+    // Extract the prompt name from the path
+    let path = req.uri.split('?').next().unwrap_or("");
+    let name = path
+        .split('/')
+        .nth(3)
+        .ok_or_else(|| "Invalid prompt name".to_string())?
+        .to_string();
+
+    match req.method.as_str() {
+        "GET" => match state.store.get_system_prompt(&name) {
+            Ok(Some(prompt)) => json_response(200, &json!({ "prompt": prompt }), state),
+            Ok(None) => not_found(),
+            Err(e) => Err(e.to_string()),
+        },
+        "DELETE" => match state.store.delete_system_prompt(&name) {
+            Ok(_) => json_response(200, &json!({ "success": true, "name": name }), state),
+            Err(e) => json_response(404, &json!({ "error": e.to_string() }), state),
+        },
+        _ => {
+            let response = ClientHttpResponse {
+                status: 405,
+                headers: vec![
+                    ("Content-Type".to_string(), "application/json".to_string()),
+                    ("Allow".to_string(), "GET, DELETE".to_string()),
+                ],
+                body: Some(
+                    serde_json::to_vec(&json!({
+                        "error": "Method not allowed"
+                    }))
+                    .unwrap(),
+                ),
+            };
+            Ok((Some(serde_json::to_vec(state).unwrap()), (response,)))
+        }
+    }
+}
+
+fn handle_usage_api(
+    req: &ClientHttpRequest,
+    state: &mut State,
+) -> Result<(Option<Vec<u8>>, (ClientHttpResponse,)), String> {
+    if req.method != "GET" {
+        return method_not_allowed("GET", state);
+    }
+
+    let ledger = UsageLedger::load(&state.store).map_err(|e| e.to_string())?;
+    json_response(200, &json!({ "usage": ledger }), state)
+}
+
+fn handle_budgets_api(
+    req: &ClientHttpRequest,
+    state: &mut State,
+) -> Result<(Option<Vec<u8>>, (ClientHttpResponse,)), String> {
+    match req.method.as_str() {
+        "GET" => {
+            let budgets = state.budgets.clone();
+            json_response(200, &json!({ "budgets": budgets }), state)
+        }
+        "PUT" => {
+            let data = parse_json_body(req)?;
+            let budgets: Budgets = match serde_json::from_value(data) {
+                Ok(budgets) => budgets,
+                Err(e) => {
+                    return json_response(
+                        400,
+                        &json!({ "error": format!("Invalid budgets: {}", e) }),
+                        state,
+                    )
+                }
+            };
+            state.set_budgets(budgets).map_err(|e| e.to_string())?;
+
+            let budgets = state.budgets.clone();
+            json_response(200, &json!({ "budgets": budgets }), state)
+        }
+        _ => method_not_allowed("GET, PUT", state),
+    }
+}
+
+fn handle_search_api(
+    req: &ClientHttpRequest,
+    state: &mut State,
+) -> Result<(Option<Vec<u8>>, (ClientHttpResponse,)), String> {
+    if req.method != "GET" {
+        return method_not_allowed("GET", state);
+    }
+
+    let query = match query_param(&req.uri, "q") {
+        Some(query) if !query.trim().is_empty() => query,
+        _ => {
+            return json_response(
+                400,
+                &json!({ "error": "Missing 'q' query parameter" }),
+                state,
+            )
+        }
+    };
+    let chat_id = query_param(&req.uri, "chat_id");
+    let limit = query_param(&req.uri, "limit")
+        .and_then(|limit| limit.parse().ok())
+        .unwrap_or(DEFAULT_SEARCH_LIMIT);
+
+    let results = state
+        .store
+        .search(&query, chat_id.as_deref(), limit)
+        .map_err(|e| e.to_string())?;
+    json_response(200, &json!({ "query": query, "results": results }), state)
+}
+
+fn handle_import_api(
+    req: &ClientHttpRequest,
+    state: &mut State,
+) -> Result<(Option<Vec<u8>>, (ClientHttpResponse,)), String> {
+    if req.method != "POST" {
+        return method_not_allowed("POST", state);
+    }
+
+    // Without a format each conversation is recognized by its fields
+    let format = match query_param(&req.uri, "format") {
+        Some(format) => match ImportFormat::parse(&format) {
+            Some(format) => Some(format),
+            None => {
+                return json_response(
+                    400,
+                    &json!({ "error": format!("Unknown import format: {}", format) }),
+                    state,
+                )
+            }
+        },
+        None => None,
+    };
+
+    let body = parse_json_body(req)?;
+    let conversations = match import::parse(&body, format) {
+        Ok(conversations) => conversations,
+        Err(e) => return json_response(400, &json!({ "error": e }), state),
+    };
+    let message_counts: Vec<usize> = conversations
+        .iter()
+        .map(|conversation| conversation.messages.len())
+        .collect();
+
+    let chats = state
+        .import_conversations(conversations)
+        .map_err(|e| e.to_string())?;
+    let imported: Vec<Value> = chats
+        .iter()
+        .zip(message_counts)
+        .map(|(chat_info, messages)| {
+            json!({
+                "id": chat_info.id,
+                "name": chat_info.name,
+                "head": chat_info.head,
+                "messages": messages,
+            })
+        })
+        .collect();
+
+    json_response(201, &json!({ "chats": imported }), state)
+}
+
+fn handle_backup_api(
+    req: &ClientHttpRequest,
+    state: &mut State,
+) -> Result<(Option<Vec<u8>>, (ClientHttpResponse,)), String> {
+    if req.method != "GET" {
+        return method_not_allowed("GET", state);
+    }
+
+    let archive = backup::create(&state.store.store_id).map_err(|e| e.to_string())?;
+    let response = ClientHttpResponse {
+        status: 200,
+        headers: vec![
+            ("Content-Type".to_string(), "application/json".to_string()),
+            (
+                "Content-Disposition".to_string(),
+                format!(
+                    "attachment; filename=\"chat-backup-{}.json\"",
+                    archive.created_at
+                ),
+            ),
+        ],
+        body: Some(serde_json::to_vec(&archive).map_err(|e| e.to_string())?),
+    };
+    Ok((Some(serde_json::to_vec(state).unwrap()), (response,)))
+}
+
+fn handle_restore_api(
+    req: &ClientHttpRequest,
+    state: &mut State,
+) -> Result<(Option<Vec<u8>>, (ClientHttpResponse,)), String> {
+    if req.method != "POST" {
+        return method_not_allowed("POST", state);
+    }
+
+    let body = parse_json_body(req)?;
+    let archive: Archive = match serde_json::from_value(body) {
+        Ok(archive) => archive,
+        Err(e) => {
+            return json_response(
+                400,
+                &json!({ "error": format!("Invalid backup archive: {}", e) }),
+                state,
+            )
+        }
+    };
+
+    match state.restore_backup(&archive) {
+        Ok(report) => json_response(200, &json!(report), state),
+        Err(e) => json_response(
+            422,
+            &json!({ "error": e.message, "problems": e.problems }),
+            state,
+        ),
+    }
+}
+
+fn handle_gc_api(
+    req: &ClientHttpRequest,
+    state: &mut State,
+) -> Result<(Option<Vec<u8>>, (ClientHttpResponse,)), String> {
+    // GET only reports; POST also removes the orphaned labels
+    let sweep = match req.method.as_str() {
+        "GET" => false,
+        "POST" => true,
+        _ => return method_not_allowed("GET, POST", state),
+    };
+
+    let report = gc::collect(&state.store, sweep).map_err(|e| e.to_string())?;
+    json_response(200, &json!(report), state)
+}
+
+fn handle_cache_api(
+    req: &ClientHttpRequest,
+    state: &mut State,
+) -> Result<(Option<Vec<u8>>, (ClientHttpResponse,)), String> {
+    if req.method != "GET" {
+        return method_not_allowed("GET", state);
+    }
+
+    let cache = &state.store.cache;
+    let body = json!({
+        "capacity": cache.capacity(),
+        "hits": cache.metrics.hits,
+        "misses": cache.metrics.misses,
+        "evictions": cache.metrics.evictions,
+        "hit_rate": cache.metrics.hit_rate(),
+    });
+    json_response(200, &body, state)
+}
+
+fn handle_trash_api(
+    req: &ClientHttpRequest,
+    state: &mut State,
+) -> Result<(Option<Vec<u8>>, (ClientHttpResponse,)), String> {
+    match req.method.as_str() {
+        "GET" => {
+            let trash = state.store.list_trash().map_err(|e| e.to_string())?;
+            json_response(
+                200,
+                &json!({
+                    "retention_days": state.trash_retention_days,
+                    "chats": trash,
+                }),
+                state,
+            )
+        }
+        "DELETE" => {
+            let purged = state.purge_trash(None).map_err(|e| e.to_string())?;
+            json_response(200, &json!({ "purged": purged }), state)
+        }
+        _ => method_not_allowed("GET, DELETE", state),
+    }
+}
+
+fn handle_trash_detail_api(
+    req: &ClientHttpRequest,
+    state: &mut State,
+) -> Result<(Option<Vec<u8>>, (ClientHttpResponse,)), String> {
+    let path_parts: Vec<&str> = req.uri.split('?').next().unwrap_or("").split('/').collect();
+    let chat_id = path_parts
+        .get(3)
+        .ok_or_else(|| "Invalid chat ID".to_string())?
+        .to_string();
+
+    match (req.method.as_str(), path_parts.get(4)) {
+        ("POST", Some(&"restore")) => match state.restore_chat(&chat_id) {
+            Ok(chat_info) => json_response(200, &json!({ "chat": chat_info }), state),
+            Err(e) => json_response(404, &json!({ "error": e.to_string() }), state),
+        },
+        ("DELETE", None) => {
+            let purged = state
+                .purge_trash(Some(&chat_id))
+                .map_err(|e| e.to_string())?;
+            if purged.is_empty() {
+                return not_found();
+            }
+            json_response(200, &json!({ "purged": purged }), state)
+        }
+        (_, Some(&"restore")) => method_not_allowed("POST", state),
+        _ => method_not_allowed("DELETE", state),
+    }
+}
+
+fn handle_chat_usage_api(
+    req: &ClientHttpRequest,
+    state: &mut State,
+    chat_id: &str,
+) -> Result<(Option<Vec<u8>>, (ClientHttpResponse,)), String> {
+    if req.method != "GET" {
+        return method_not_allowed("GET", state);
+    }
+
+    let ledger = UsageLedger::load(&state.store).map_err(|e| e.to_string())?;
+    json_response(
+        200,
+        &json!({
+            "chat_id": chat_id,
+            "usage": ledger.chat(chat_id),
+        }),
+        state,
+    )
+}
+
+// Read a parameter from the query string, decoding %XX escapes and '+'
+fn query_param(uri: &str, name: &str) -> Option<String> {
+    let query = uri.split_once('?')?.1;
+    let value = query.split('&').find_map(|pair| {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        (key == name).then_some(value)
+    })?;
+
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+                match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        i += 2;
+                    }
+                    None => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    Some(String::from_utf8_lossy(&decoded).into_owned())
+}
+
+fn parse_json_body(req: &ClientHttpRequest) -> Result<Value, String> {
+    let body = match &req.body {
+        Some(body) => String::from_utf8(body.clone())
+            .map_err(|_| "Invalid UTF-8 in request body".to_string())?,
+        None => return Err("Missing request body".to_string()),
+    };
+
+    serde_json::from_str(&body).map_err(|_| "Invalid JSON in request body".to_string())
+}
+
+fn json_response(
+    status: u16,
+    body: &Value,
+    state: &mut State,
+) -> Result<(Option<Vec<u8>>, (ClientHttpResponse,)), String> {
+    let response = ClientHttpResponse {
+        status,
+        headers: vec![
+            ("Content-Type".to_string(), "application/json".to_string()),
+            ("Cache-Control".to_string(), "no-cache".to_string()),
+        ],
+        body: Some(serde_json::to_vec(body).unwrap()),
+    };
+    Ok((Some(serde_json::to_vec(state).unwrap()), (response,)))
+}
+
+fn method_not_allowed(
+    allow: &str,
+    state: &mut State,
+) -> Result<(Option<Vec<u8>>, (ClientHttpResponse,)), String> {
+    let response = ClientHttpResponse {
+        status: 405,
+        headers: vec![
+            ("Content-Type".to_string(), "application/json".to_string()),
+            ("Allow".to_string(), allow.to_string()),
+        ],
+        body: Some(
+            serde_json::to_vec(&json!({
+                "error": "Method not allowed"
+            }))
+            .unwrap(),
+        ),
+    };
+    Ok((Some(serde_json::to_vec(state).unwrap()), (response,)))
+}
+
 fn not_found() -> Result<(Option<Vec<u8>>, (ClientHttpResponse,)), String> {
     let response = ClientHttpResponse {
         status: 404,
@@ -374,3 +1099,24 @@ fn not_found() -> Result<(Option<Vec<u8>>, (ClientHttpResponse,)), String> {
     };
     Ok((None, (response,)))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chat_routes_ignore_the_query_string() {
+        let uri = "/api/chats/a1b2/export?format=html";
+        assert_eq!(chat_detail_route(uri), Some(("a1b2", Some("export"))));
+        assert_eq!(query_param(uri, "format").as_deref(), Some("html"));
+        assert_eq!(
+            chat_detail_route("/api/chats/a1b2/usage?day=2025-01-01"),
+            Some(("a1b2", Some("usage")))
+        );
+        assert_eq!(
+            chat_detail_route("/api/chats/a1b2?x=1"),
+            Some(("a1b2", None))
+        );
+        assert_eq!(chat_detail_route("/api/chats/"), None);
+    }
+}
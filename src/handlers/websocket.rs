@@ -1,7 +1,13 @@
+use crate::api::error::GenerationError;
 use crate::bindings::ntwk::theater::runtime::log;
 use crate::bindings::ntwk::theater::types::Json;
 use crate::bindings::ntwk::theater::websocket_types::{MessageType, WebsocketMessage};
+use crate::context::ContextStrategy;
+use crate::messages::search::DEFAULT_SEARCH_LIMIT;
+use crate::messages::{GenerationParams, SystemPrompt};
 use crate::state::State;
+use crate::usage::budget::Budgets;
+use crate::usage::UsageLedger;
 use serde_json::{json, Value};
 
 // Define a new type for WebsocketResponse to match the old API
@@ -27,6 +33,72 @@ fn create_messages_updated_response(state: &State) -> WebsocketResponse {
     }
 }
 
+// Structured error for a failed generation, followed by the (possibly moved) head
+fn create_generation_error_response(
+    state: &State,
+    error: &(dyn std::error::Error + 'static),
+) -> WebsocketResponse {
+    let error = GenerationError::from_boxed(error);
+    let mut response = create_messages_updated_response(state);
+    response.messages.insert(
+        0,
+        WebsocketMessage {
+            ty: MessageType::Text,
+            text: Some(
+                state
+                    .generation_error_frame(&error, state.head.as_deref())
+                    .to_string(),
+            ),
+            data: None,
+        },
+    );
+    response
+}
+
+// Reply to the caller of a failed generation. Generation errors have already been broadcast
+// to every connection, the caller included, so only the (possibly moved) head is sent;
+// anything else, such as an unknown message to regenerate from, is reported here.
+fn create_generation_failed_response(
+    state: &State,
+    error: &(dyn std::error::Error + 'static),
+) -> WebsocketResponse {
+    if error.downcast_ref::<GenerationError>().is_some() {
+        return create_messages_updated_response(state);
+    }
+    let mut response = create_error_response(error.to_string());
+    response
+        .messages
+        .extend(create_messages_updated_response(state).messages);
+    response
+}
+
+// Helper function to create an error response
+fn create_error_response(message: String) -> WebsocketResponse {
+    WebsocketResponse {
+        messages: vec![WebsocketMessage {
+            ty: MessageType::Text,
+            text: Some(
+                json!({
+                    "type": "error",
+                    "message": message
+                })
+                .to_string(),
+            ),
+            data: None,
+        }],
+    }
+}
+
+// Missing or null params mean "no overrides"
+fn parse_params(value: &Value) -> Result<Option<GenerationParams>, String> {
+    if value.is_null() {
+        return Ok(None);
+    }
+    serde_json::from_value(value.clone())
+        .map(Some)
+        .map_err(|e| format!("Invalid generation params: {}", e))
+}
+
 pub fn handle_message(
     msg: WebsocketMessage,
     state: Json,
@@ -71,6 +143,30 @@ pub fn handle_message(
                                 default_response(&current_state)
                             }
                         }
+                        Some("list_trash") => handle_list_trash(&current_state),
+                        Some("restore_chat") => {
+                            if let Some(chat_id) = command["chat_id"].as_str() {
+                                handle_restore_chat(&mut current_state, chat_id)
+                            } else {
+                                default_response(&current_state)
+                            }
+                        }
+                        Some("empty_trash") => handle_empty_trash(&mut current_state),
+                        Some("fork_chat") => {
+                            // The source chat defaults to the one currently open
+                            let chat_id = command["chat_id"]
+                                .as_str()
+                                .map(String::from)
+                                .or_else(|| current_state.current_chat_id.clone());
+                            let name = command["name"].as_str().map(String::from);
+                            if let (Some(chat_id), Some(message_id)) =
+                                (chat_id, command["message_id"].as_str())
+                            {
+                                handle_fork_chat(&mut current_state, &chat_id, message_id, name)
+                            } else {
+                                default_response(&current_state)
+                            }
+                        }
 
                         // Message commands
                         Some("send_message") => {
@@ -82,14 +178,201 @@ pub fn handle_message(
                         }
                         Some("generate_llm_response") => {
                             // Extract optional model ID from the message
-                            if let Some(model) = command["model_id"].as_str() {
-                                handle_generate_llm_response(&mut current_state, model.to_string())
+                            match (
+                                command["model_id"].as_str(),
+                                parse_params(&command["params"]),
+                            ) {
+                                (Some(model), Ok(params)) => handle_generate_llm_response(
+                                    &mut current_state,
+                                    model.to_string(),
+                                    params.unwrap_or_default(),
+                                ),
+                                (Some(_), Err(e)) => Ok((
+                                    Some(serde_json::to_vec(&current_state).unwrap()),
+                                    (create_error_response(e),),
+                                )),
+                                (None, _) => default_response(&current_state),
+                            }
+                        }
+                        Some("set_generation_params") => {
+                            let chat_id = command["chat_id"]
+                                .as_str()
+                                .map(String::from)
+                                .or_else(|| current_state.current_chat_id.clone());
+                            match (chat_id, parse_params(&command["params"])) {
+                                (Some(chat_id), Ok(params)) => handle_set_generation_params(
+                                    &mut current_state,
+                                    &chat_id,
+                                    params,
+                                ),
+                                (Some(_), Err(e)) => Ok((
+                                    Some(serde_json::to_vec(&current_state).unwrap()),
+                                    (create_error_response(e),),
+                                )),
+                                (None, _) => default_response(&current_state),
+                            }
+                        }
+
+                        Some("set_auto_title") => {
+                            let chat_id = command["chat_id"]
+                                .as_str()
+                                .map(String::from)
+                                .or_else(|| current_state.current_chat_id.clone());
+                            match (chat_id, command["enabled"].as_bool()) {
+                                (Some(chat_id), Some(enabled)) => {
+                                    handle_set_auto_title(&mut current_state, &chat_id, enabled)
+                                }
+                                _ => default_response(&current_state),
+                            }
+                        }
+                        Some("set_context_strategy") => {
+                            let chat_id = command["chat_id"]
+                                .as_str()
+                                .map(String::from)
+                                .or_else(|| current_state.current_chat_id.clone());
+                            let strategy = match &command["strategy"] {
+                                Value::Null => Ok(None),
+                                value => serde_json::from_value(value.clone())
+                                    .map(Some)
+                                    .map_err(|e| format!("Invalid context strategy: {}", e)),
+                            };
+                            match (chat_id, strategy) {
+                                (Some(chat_id), Ok(strategy)) => handle_set_context_strategy(
+                                    &mut current_state,
+                                    &chat_id,
+                                    strategy,
+                                ),
+                                (Some(_), Err(e)) => Ok((
+                                    Some(serde_json::to_vec(&current_state).unwrap()),
+                                    (create_error_response(e),),
+                                )),
+                                (None, _) => default_response(&current_state),
+                            }
+                        }
+                        Some(command_type @ ("pin_message" | "unpin_message")) => {
+                            let chat_id = command["chat_id"]
+                                .as_str()
+                                .map(String::from)
+                                .or_else(|| current_state.current_chat_id.clone());
+                            match (chat_id, command["message_id"].as_str()) {
+                                (Some(chat_id), Some(message_id)) => handle_set_message_pinned(
+                                    &mut current_state,
+                                    &chat_id,
+                                    message_id,
+                                    command_type == "pin_message",
+                                ),
+                                _ => default_response(&current_state),
+                            }
+                        }
+
+                        // Branching commands
+                        Some("edit_message") => {
+                            if let (Some(message_id), Some(content)) =
+                                (command["message_id"].as_str(), command["content"].as_str())
+                            {
+                                handle_edit_message(&mut current_state, message_id, content)
+                            } else {
+                                default_response(&current_state)
+                            }
+                        }
+                        Some("regenerate_response") => {
+                            if let (Some(message_id), Some(model)) =
+                                (command["message_id"].as_str(), command["model_id"].as_str())
+                            {
+                                handle_regenerate_response(
+                                    &mut current_state,
+                                    message_id,
+                                    model.to_string(),
+                                )
+                            } else {
+                                default_response(&current_state)
+                            }
+                        }
+                        Some("summarize_chat") => handle_summarize_chat(&mut current_state),
+                        Some("list_branches") => {
+                            if let Some(message_id) = command["message_id"].as_str() {
+                                handle_list_branches(&mut current_state, message_id)
+                            } else {
+                                default_response(&current_state)
+                            }
+                        }
+                        Some("switch_branch") => {
+                            if let Some(message_id) = command["message_id"].as_str() {
+                                handle_switch_branch(&mut current_state, message_id)
+                            } else {
+                                default_response(&current_state)
+                            }
+                        }
+
+                        // System prompt commands
+                        Some("set_system_prompt") => {
+                            // Either literal text or the name of a saved prompt; neither clears it
+                            let chat_id = command["chat_id"]
+                                .as_str()
+                                .map(String::from)
+                                .or_else(|| current_state.current_chat_id.clone());
+                            let system_prompt = command["system_prompt"].as_str().map(String::from);
+                            let prompt_name = command["prompt_name"].as_str();
+                            if let Some(chat_id) = chat_id {
+                                handle_set_system_prompt(
+                                    &mut current_state,
+                                    &chat_id,
+                                    system_prompt,
+                                    prompt_name,
+                                )
+                            } else {
+                                default_response(&current_state)
+                            }
+                        }
+                        Some("list_system_prompts") => handle_list_system_prompts(&current_state),
+                        Some("save_system_prompt") => {
+                            if let (Some(name), Some(content)) =
+                                (command["name"].as_str(), command["content"].as_str())
+                            {
+                                handle_save_system_prompt(&current_state, name, content)
+                            } else {
+                                default_response(&current_state)
+                            }
+                        }
+                        Some("delete_system_prompt") => {
+                            if let Some(name) = command["name"].as_str() {
+                                handle_delete_system_prompt(&current_state, name)
                             } else {
                                 default_response(&current_state)
                             }
                         }
 
                         Some("list_models") => handle_list_models(&current_state),
+                        Some("list_tools") => handle_list_tools(&current_state),
+                        Some("get_usage") => {
+                            let chat_id = command["chat_id"].as_str();
+                            handle_get_usage(&current_state, chat_id)
+                        }
+                        Some("search") => {
+                            if let Some(query) = command["query"].as_str() {
+                                let limit = command["limit"]
+                                    .as_u64()
+                                    .map_or(DEFAULT_SEARCH_LIMIT, |limit| limit as usize);
+                                handle_search(
+                                    &mut current_state,
+                                    query,
+                                    command["chat_id"].as_str(),
+                                    limit,
+                                )
+                            } else {
+                                default_response(&current_state)
+                            }
+                        }
+                        Some("get_budgets") => handle_get_budgets(&current_state),
+                        Some("set_budgets") => {
+                            match serde_json::from_value::<Budgets>(command["budgets"].clone()) {
+                                Ok(budgets) => handle_set_budgets(&mut current_state, budgets),
+                                Err(e) => Ok((
+                                    Some(serde_json::to_vec(&current_state).unwrap()),
+                                    (create_error_response(format!("Invalid budgets: {}", e)),),
+                                )),
+                            }
+                        }
                         Some("get_message") => {
                             if let Some(message_id) = command["message_id"].as_str() {
                                 handle_get_message(&mut current_state, message_id)
@@ -114,23 +397,17 @@ pub fn handle_message(
 
 // Chat management handlers
 fn handle_list_chats(state: &State) -> Result<(Option<Vec<u8>>, (WebsocketResponse,)), String> {
-    // Send a chats update message to the client
-    if let Err(e) = state.notify_chats_update() {
-        log(&format!("Failed to notify chats update: {}", e));
-    }
+    // The index is loaded once; the same frame goes to every client and back as the reply
+    let chats = state.store.list_chat_summaries().unwrap_or_default();
+    let update = json!({
+        "type": "chats_update",
+        "chats": chats,
+        "current_chat_id": state.current_chat_id
+    })
+    .to_string();
 
-    // Return a direct response as well (redundant but safe)
-    let mut chats = Vec::new();
-    if let Ok(chat_ids) = state.store.list_chat_ids() {
-        for chat_id in chat_ids {
-            if let Ok(Some(chat_info)) = state.store.get_chat_info(&chat_id) {
-                chats.push(json!({
-                    "id": chat_info.id,
-                    "name": chat_info.name,
-                    "icon": chat_info.icon,
-                }));
-            }
-        }
+    if let Err(e) = state.broadcast_websocket_message(&update) {
+        log(&format!("Failed to notify chats update: {}", e));
     }
 
     Ok((
@@ -138,14 +415,7 @@ fn handle_list_chats(state: &State) -> Result<(Option<Vec<u8>>, (WebsocketRespon
         (WebsocketResponse {
             messages: vec![WebsocketMessage {
                 ty: MessageType::Text,
-                text: Some(
-                    json!({
-                        "type": "chats_update",
-                        "chats": chats,
-                        "current_chat_id": state.current_chat_id
-                    })
-                    .to_string(),
-                ),
+                text: Some(update),
                 data: None,
             }],
         },),
@@ -220,6 +490,64 @@ fn handle_create_chat(
     }
 }
 
+fn handle_fork_chat(
+    state: &mut State,
+    chat_id: &str,
+    message_id: &str,
+    name: Option<String>,
+) -> Result<(Option<Vec<u8>>, (WebsocketResponse,)), String> {
+    match state.fork_chat(chat_id, message_id, name) {
+        Ok(chat_info) => {
+            // Notify all clients about chats update
+            if let Err(e) = state.notify_chats_update() {
+                log(&format!("Failed to notify chats update: {}", e));
+            }
+
+            Ok((
+                Some(serde_json::to_vec(state).unwrap()),
+                (WebsocketResponse {
+                    messages: vec![
+                        WebsocketMessage {
+                            ty: MessageType::Text,
+                            text: Some(
+                                json!({
+                                    "type": "chat_created",
+                                    "chat": {
+                                        "id": chat_info.id,
+                                        "name": chat_info.name,
+                                        "forked_from": chat_info.forked_from,
+                                    }
+                                })
+                                .to_string(),
+                            ),
+                            data: None,
+                        },
+                        WebsocketMessage {
+                            ty: MessageType::Text,
+                            text: Some(
+                                json!({
+                                    "type": "messages_updated",
+                                    "head": state.head,
+                                    "current_chat_id": state.current_chat_id
+                                })
+                                .to_string(),
+                            ),
+                            data: None,
+                        },
+                    ],
+                },),
+            ))
+        }
+        Err(e) => {
+            log(&format!("Failed to fork chat: {}", e));
+            Ok((
+                Some(serde_json::to_vec(state).unwrap()),
+                (create_error_response(format!("Failed to fork chat: {}", e)),),
+            ))
+        }
+    }
+}
+
 fn handle_switch_chat(
     state: &mut State,
     chat_id: &str,
@@ -262,8 +590,9 @@ fn handle_rename_chat(
     // Get the current chat info
     match state.store.get_chat_info(chat_id) {
         Ok(Some(mut chat_info)) => {
-            // Update the name
+            // Update the name; a chat named by hand is never auto-titled
             chat_info.name = name.to_string();
+            chat_info.title_locked = true;
             // Save the updated chat info
             if let Err(e) = state.store.update_chat_info(&chat_info) {
                 log(&format!("Failed to update chat info: {}", e));
@@ -432,8 +761,9 @@ fn handle_send_message(
 fn handle_generate_llm_response(
     state: &mut State,
     model_id: String,
+    params: GenerationParams,
 ) -> Result<(Option<Vec<u8>>, (WebsocketResponse,)), String> {
-    match state.generate_llm_response(model_id) {
+    match state.generate_llm_response(model_id, params) {
         Ok(_) => {
             // Response success - head will have been updated
             Ok((
@@ -442,23 +772,127 @@ fn handle_generate_llm_response(
             ))
         }
         Err(e) => {
-            // Error already logged and notified by the generate_llm_response method
-            // Just return the current state
+            log(&format!("Failed to generate response: {}", e));
             Ok((
                 Some(serde_json::to_vec(state).unwrap()),
-                (WebsocketResponse {
-                    messages: vec![WebsocketMessage {
-                        ty: MessageType::Text,
-                        text: Some(
-                            json!({
-                                "type": "error",
-                                "message": format!("Failed to generate LLM response: {}", e)
-                            })
-                            .to_string(),
-                        ),
-                        data: None,
-                    }],
-                },),
+                (create_generation_failed_response(state, e.as_ref()),),
+            ))
+        }
+    }
+}
+
+fn handle_edit_message(
+    state: &mut State,
+    message_id: &str,
+    content: &str,
+) -> Result<(Option<Vec<u8>>, (WebsocketResponse,)), String> {
+    match state.edit_message(message_id, content) {
+        Ok(_) => Ok((
+            Some(serde_json::to_vec(state).unwrap()),
+            (create_messages_updated_response(state),),
+        )),
+        Err(e) => {
+            log(&format!("Failed to edit message: {}", e));
+            Ok((
+                Some(serde_json::to_vec(state).unwrap()),
+                (create_error_response(format!(
+                    "Failed to edit message: {}",
+                    e
+                )),),
+            ))
+        }
+    }
+}
+
+fn handle_regenerate_response(
+    state: &mut State,
+    message_id: &str,
+    model_id: String,
+) -> Result<(Option<Vec<u8>>, (WebsocketResponse,)), String> {
+    match state.regenerate_response(message_id, model_id) {
+        Ok(_) => Ok((
+            Some(serde_json::to_vec(state).unwrap()),
+            (create_messages_updated_response(state),),
+        )),
+        Err(e) => {
+            log(&format!("Failed to regenerate response: {}", e));
+            Ok((
+                Some(serde_json::to_vec(state).unwrap()),
+                (create_generation_failed_response(state, e.as_ref()),),
+            ))
+        }
+    }
+}
+
+fn handle_summarize_chat(
+    state: &mut State,
+) -> Result<(Option<Vec<u8>>, (WebsocketResponse,)), String> {
+    match state.summarize_chat() {
+        Ok(_) => Ok((
+            Some(serde_json::to_vec(state).unwrap()),
+            (create_messages_updated_response(state),),
+        )),
+        Err(e) => {
+            log(&format!("Failed to summarize chat: {}", e));
+            Ok((
+                Some(serde_json::to_vec(state).unwrap()),
+                (create_generation_error_response(state, e.as_ref()),),
+            ))
+        }
+    }
+}
+
+fn handle_list_branches(
+    state: &mut State,
+    message_id: &str,
+) -> Result<(Option<Vec<u8>>, (WebsocketResponse,)), String> {
+    match state.list_branches(message_id) {
+        Ok(branches) => Ok((
+            Some(serde_json::to_vec(state).unwrap()),
+            (WebsocketResponse {
+                messages: vec![WebsocketMessage {
+                    ty: MessageType::Text,
+                    text: Some(
+                        json!({
+                            "type": "branches",
+                            "branches": branches
+                        })
+                        .to_string(),
+                    ),
+                    data: None,
+                }],
+            },),
+        )),
+        Err(e) => {
+            log(&format!("Failed to list branches: {}", e));
+            Ok((
+                Some(serde_json::to_vec(state).unwrap()),
+                (create_error_response(format!(
+                    "Failed to list branches: {}",
+                    e
+                )),),
+            ))
+        }
+    }
+}
+
+fn handle_switch_branch(
+    state: &mut State,
+    message_id: &str,
+) -> Result<(Option<Vec<u8>>, (WebsocketResponse,)), String> {
+    match state.switch_branch(message_id) {
+        Ok(_) => Ok((
+            Some(serde_json::to_vec(state).unwrap()),
+            (create_messages_updated_response(state),),
+        )),
+        Err(e) => {
+            log(&format!("Failed to switch branch: {}", e));
+            Ok((
+                Some(serde_json::to_vec(state).unwrap()),
+                (create_error_response(format!(
+                    "Failed to switch branch: {}",
+                    e
+                )),),
             ))
         }
     }
@@ -528,37 +962,40 @@ fn handle_get_head(state: &State) -> Result<(Option<Vec<u8>>, (WebsocketResponse
 }
 
 fn handle_list_models(state: &State) -> Result<(Option<Vec<u8>>, (WebsocketResponse,)), String> {
-    // Get OpenRouter models
-    let openrouter_models = match state.openrouter_client.list_available_models() {
+    // Get models from every configured provider
+    let models = match state.providers.list_available_models() {
         Ok(models) => {
             log(&format!(
-                "[DEBUG] Successfully retrieved {} OpenRouter models",
+                "[DEBUG] Successfully retrieved {} models",
                 models.len()
             ));
             models
         }
         Err(e) => {
-            log(&format!("[ERROR] Failed to list OpenRouter models: {}", e));
-            return Err(format!("Failed to list OpenRouter models: {}", e));
+            log(&format!("[ERROR] Failed to list models: {}", e));
+            return Err(format!("Failed to list models: {}", e));
         }
     };
 
-    // Add provider field to OpenRouter models if not already present
-    let openrouter_models_with_provider: Vec<Value> = openrouter_models
+    let all_models: Vec<Value> = models
         .iter()
         .map(|model| {
+            let pricing = state
+                .providers
+                .for_model(&model.id)
+                .ok()
+                .and_then(|provider| provider.pricing(&model.id));
             json!({
                 "id": model.id,
                 "display_name": model.display_name,
                 "max_tokens": model.max_tokens,
-                "provider": model.provider.clone().unwrap_or_else(|| "openrouter".to_string())
+                "provider": model.provider,
+                "tools_enabled": model.tools_enabled,
+                "pricing": pricing,
             })
         })
         .collect();
 
-    // Combine all models
-    let all_models: Vec<Value> = [openrouter_models_with_provider].concat();
-
     // Log the combined models for debugging
     log(&format!(
         "[DEBUG] Total models available: {}",
@@ -595,6 +1032,509 @@ fn handle_list_models(state: &State) -> Result<(Option<Vec<u8>>, (WebsocketRespo
     ))
 }
 
+fn handle_list_tools(state: &State) -> Result<(Option<Vec<u8>>, (WebsocketResponse,)), String> {
+    let servers: Vec<Value> = state
+        .mcp_servers
+        .iter()
+        .map(|server| {
+            json!({
+                "name": server.name(),
+                "server_info": server.server_info,
+                "tools": server.tools,
+            })
+        })
+        .collect();
+
+    Ok((
+        Some(serde_json::to_vec(state).unwrap()),
+        (WebsocketResponse {
+            messages: vec![WebsocketMessage {
+                ty: MessageType::Text,
+                text: Some(
+                    json!({
+                        "type": "tools_list",
+                        "servers": servers
+                    })
+                    .to_string(),
+                ),
+                data: None,
+            }],
+        },),
+    ))
+}
+
+fn handle_set_generation_params(
+    state: &mut State,
+    chat_id: &str,
+    params: Option<GenerationParams>,
+) -> Result<(Option<Vec<u8>>, (WebsocketResponse,)), String> {
+    match state.set_generation_params(chat_id, params) {
+        Ok(chat_info) => Ok((
+            Some(serde_json::to_vec(state).unwrap()),
+            (WebsocketResponse {
+                messages: vec![WebsocketMessage {
+                    ty: MessageType::Text,
+                    text: Some(
+                        json!({
+                            "type": "generation_params_updated",
+                            "chat_id": chat_info.id,
+                            "generation_params": chat_info.generation_params,
+                        })
+                        .to_string(),
+                    ),
+                    data: None,
+                }],
+            },),
+        )),
+        Err(e) => {
+            log(&format!("Failed to set generation params: {}", e));
+            Ok((
+                Some(serde_json::to_vec(state).unwrap()),
+                (create_error_response(format!(
+                    "Failed to set generation params: {}",
+                    e
+                )),),
+            ))
+        }
+    }
+}
+
+fn handle_set_auto_title(
+    state: &mut State,
+    chat_id: &str,
+    enabled: bool,
+) -> Result<(Option<Vec<u8>>, (WebsocketResponse,)), String> {
+    match state.set_auto_title(chat_id, enabled) {
+        Ok(chat_info) => Ok((
+            Some(serde_json::to_vec(state).unwrap()),
+            (WebsocketResponse {
+                messages: vec![WebsocketMessage {
+                    ty: MessageType::Text,
+                    text: Some(
+                        json!({
+                            "type": "auto_title_updated",
+                            "chat_id": chat_info.id,
+                            "title_locked": chat_info.title_locked,
+                        })
+                        .to_string(),
+                    ),
+                    data: None,
+                }],
+            },),
+        )),
+        Err(e) => {
+            log(&format!("Failed to update automatic titling: {}", e));
+            Ok((
+                Some(serde_json::to_vec(state).unwrap()),
+                (create_error_response(format!(
+                    "Failed to update automatic titling: {}",
+                    e
+                )),),
+            ))
+        }
+    }
+}
+
+fn handle_set_context_strategy(
+    state: &mut State,
+    chat_id: &str,
+    strategy: Option<ContextStrategy>,
+) -> Result<(Option<Vec<u8>>, (WebsocketResponse,)), String> {
+    match state.set_context_strategy(chat_id, strategy) {
+        Ok(chat_info) => Ok((
+            Some(serde_json::to_vec(state).unwrap()),
+            (WebsocketResponse {
+                messages: vec![WebsocketMessage {
+                    ty: MessageType::Text,
+                    text: Some(
+                        json!({
+                            "type": "context_strategy_updated",
+                            "chat_id": chat_info.id,
+                            "context_strategy": chat_info.context_strategy,
+                        })
+                        .to_string(),
+                    ),
+                    data: None,
+                }],
+            },),
+        )),
+        Err(e) => {
+            log(&format!("Failed to set context strategy: {}", e));
+            Ok((
+                Some(serde_json::to_vec(state).unwrap()),
+                (create_error_response(format!(
+                    "Failed to set context strategy: {}",
+                    e
+                )),),
+            ))
+        }
+    }
+}
+
+fn handle_set_message_pinned(
+    state: &mut State,
+    chat_id: &str,
+    message_id: &str,
+    pinned: bool,
+) -> Result<(Option<Vec<u8>>, (WebsocketResponse,)), String> {
+    match state.set_message_pinned(chat_id, message_id, pinned) {
+        Ok(chat_info) => Ok((
+            Some(serde_json::to_vec(state).unwrap()),
+            (WebsocketResponse {
+                messages: vec![WebsocketMessage {
+                    ty: MessageType::Text,
+                    text: Some(
+                        json!({
+                            "type": "pinned_messages_updated",
+                            "chat_id": chat_info.id,
+                            "pinned_messages": chat_info.pinned_messages,
+                        })
+                        .to_string(),
+                    ),
+                    data: None,
+                }],
+            },),
+        )),
+        Err(e) => {
+            log(&format!("Failed to update pinned messages: {}", e));
+            Ok((
+                Some(serde_json::to_vec(state).unwrap()),
+                (create_error_response(format!(
+                    "Failed to update pinned messages: {}",
+                    e
+                )),),
+            ))
+        }
+    }
+}
+
+fn handle_set_system_prompt(
+    state: &mut State,
+    chat_id: &str,
+    system_prompt: Option<String>,
+    prompt_name: Option<&str>,
+) -> Result<(Option<Vec<u8>>, (WebsocketResponse,)), String> {
+    let system_prompt = match prompt_name {
+        Some(name) => match state.store.get_system_prompt(name) {
+            Ok(Some(prompt)) => Some(prompt.content),
+            Ok(None) => {
+                return Ok((
+                    Some(serde_json::to_vec(state).unwrap()),
+                    (create_error_response(format!(
+                        "System prompt {} not found",
+                        name
+                    )),),
+                ))
+            }
+            Err(e) => {
+                return Ok((
+                    Some(serde_json::to_vec(state).unwrap()),
+                    (create_error_response(format!(
+                        "Failed to load system prompt: {}",
+                        e
+                    )),),
+                ))
+            }
+        },
+        None => system_prompt,
+    };
+
+    match state.set_system_prompt(chat_id, system_prompt) {
+        Ok(chat_info) => Ok((
+            Some(serde_json::to_vec(state).unwrap()),
+            (WebsocketResponse {
+                messages: vec![WebsocketMessage {
+                    ty: MessageType::Text,
+                    text: Some(
+                        json!({
+                            "type": "system_prompt_updated",
+                            "chat_id": chat_info.id,
+                            "system_prompt": chat_info.system_prompt,
+                        })
+                        .to_string(),
+                    ),
+                    data: None,
+                }],
+            },),
+        )),
+        Err(e) => {
+            log(&format!("Failed to set system prompt: {}", e));
+            Ok((
+                Some(serde_json::to_vec(state).unwrap()),
+                (create_error_response(format!(
+                    "Failed to set system prompt: {}",
+                    e
+                )),),
+            ))
+        }
+    }
+}
+
+fn handle_list_system_prompts(
+    state: &State,
+) -> Result<(Option<Vec<u8>>, (WebsocketResponse,)), String> {
+    match state.store.list_system_prompts() {
+        Ok(prompts) => Ok((
+            Some(serde_json::to_vec(state).unwrap()),
+            (WebsocketResponse {
+                messages: vec![WebsocketMessage {
+                    ty: MessageType::Text,
+                    text: Some(
+                        json!({
+                            "type": "system_prompts",
+                            "prompts": prompts
+                        })
+                        .to_string(),
+                    ),
+                    data: None,
+                }],
+            },),
+        )),
+        Err(e) => Ok((
+            Some(serde_json::to_vec(state).unwrap()),
+            (create_error_response(format!(
+                "Failed to list system prompts: {}",
+                e
+            )),),
+        )),
+    }
+}
+
+fn handle_save_system_prompt(
+    state: &State,
+    name: &str,
+    content: &str,
+) -> Result<(Option<Vec<u8>>, (WebsocketResponse,)), String> {
+    let prompt = SystemPrompt {
+        name: name.to_string(),
+        content: content.to_string(),
+    };
+
+    match state.store.save_system_prompt(prompt) {
+        Ok(_) => handle_list_system_prompts(state),
+        Err(e) => Ok((
+            Some(serde_json::to_vec(state).unwrap()),
+            (create_error_response(format!(
+                "Failed to save system prompt: {}",
+                e
+            )),),
+        )),
+    }
+}
+
+fn handle_delete_system_prompt(
+    state: &State,
+    name: &str,
+) -> Result<(Option<Vec<u8>>, (WebsocketResponse,)), String> {
+    match state.store.delete_system_prompt(name) {
+        Ok(_) => handle_list_system_prompts(state),
+        Err(e) => Ok((
+            Some(serde_json::to_vec(state).unwrap()),
+            (create_error_response(format!(
+                "Failed to delete system prompt: {}",
+                e
+            )),),
+        )),
+    }
+}
+
+fn handle_get_usage(
+    state: &State,
+    chat_id: Option<&str>,
+) -> Result<(Option<Vec<u8>>, (WebsocketResponse,)), String> {
+    let ledger = match UsageLedger::load(&state.store) {
+        Ok(ledger) => ledger,
+        Err(e) => {
+            return Ok((
+                Some(serde_json::to_vec(state).unwrap()),
+                (create_error_response(format!(
+                    "Failed to load usage: {}",
+                    e
+                )),),
+            ))
+        }
+    };
+
+    // A chat_id narrows the answer down to that chat
+    let frame = match chat_id {
+        Some(chat_id) => json!({
+            "type": "usage",
+            "chat_id": chat_id,
+            "usage": ledger.chat(chat_id),
+        }),
+        None => json!({
+            "type": "usage",
+            "usage": ledger,
+        }),
+    };
+
+    Ok((
+        Some(serde_json::to_vec(state).unwrap()),
+        (WebsocketResponse {
+            messages: vec![WebsocketMessage {
+                ty: MessageType::Text,
+                text: Some(frame.to_string()),
+                data: None,
+            }],
+        },),
+    ))
+}
+
+fn handle_search(
+    state: &mut State,
+    query: &str,
+    chat_id: Option<&str>,
+    limit: usize,
+) -> Result<(Option<Vec<u8>>, (WebsocketResponse,)), String> {
+    match state.store.search(query, chat_id, limit) {
+        Ok(results) => Ok((
+            Some(serde_json::to_vec(state).unwrap()),
+            (WebsocketResponse {
+                messages: vec![WebsocketMessage {
+                    ty: MessageType::Text,
+                    text: Some(
+                        json!({
+                            "type": "search_results",
+                            "query": query,
+                            "results": results,
+                        })
+                        .to_string(),
+                    ),
+                    data: None,
+                }],
+            },),
+        )),
+        Err(e) => {
+            log(&format!("Search failed: {}", e));
+            Ok((
+                Some(serde_json::to_vec(state).unwrap()),
+                (create_error_response(format!("Search failed: {}", e)),),
+            ))
+        }
+    }
+}
+
+fn handle_list_trash(state: &State) -> Result<(Option<Vec<u8>>, (WebsocketResponse,)), String> {
+    let trash = match state.store.list_trash() {
+        Ok(trash) => trash,
+        Err(e) => {
+            return Ok((
+                Some(serde_json::to_vec(state).unwrap()),
+                (create_error_response(format!(
+                    "Failed to list trash: {}",
+                    e
+                )),),
+            ))
+        }
+    };
+
+    Ok((
+        Some(serde_json::to_vec(state).unwrap()),
+        (WebsocketResponse {
+            messages: vec![WebsocketMessage {
+                ty: MessageType::Text,
+                text: Some(
+                    json!({
+                        "type": "trash",
+                        "retention_days": state.trash_retention_days,
+                        "chats": trash,
+                    })
+                    .to_string(),
+                ),
+                data: None,
+            }],
+        },),
+    ))
+}
+
+fn handle_restore_chat(
+    state: &mut State,
+    chat_id: &str,
+) -> Result<(Option<Vec<u8>>, (WebsocketResponse,)), String> {
+    match state.restore_chat(chat_id) {
+        Ok(chat_info) => Ok((
+            Some(serde_json::to_vec(state).unwrap()),
+            (WebsocketResponse {
+                messages: vec![WebsocketMessage {
+                    ty: MessageType::Text,
+                    text: Some(
+                        json!({
+                            "type": "chat_restored",
+                            "chat": chat_info,
+                        })
+                        .to_string(),
+                    ),
+                    data: None,
+                }],
+            },),
+        )),
+        Err(e) => {
+            log(&format!("Failed to restore chat {}: {}", chat_id, e));
+            Ok((
+                Some(serde_json::to_vec(state).unwrap()),
+                (create_error_response(format!(
+                    "Failed to restore chat: {}",
+                    e
+                )),),
+            ))
+        }
+    }
+}
+
+fn handle_empty_trash(
+    state: &mut State,
+) -> Result<(Option<Vec<u8>>, (WebsocketResponse,)), String> {
+    if let Err(e) = state.purge_trash(None) {
+        log(&format!("Failed to empty trash: {}", e));
+        return Ok((
+            Some(serde_json::to_vec(state).unwrap()),
+            (create_error_response(format!(
+                "Failed to empty trash: {}",
+                e
+            )),),
+        ));
+    }
+
+    handle_list_trash(state)
+}
+
+fn handle_get_budgets(state: &State) -> Result<(Option<Vec<u8>>, (WebsocketResponse,)), String> {
+    Ok((
+        Some(serde_json::to_vec(state).unwrap()),
+        (WebsocketResponse {
+            messages: vec![WebsocketMessage {
+                ty: MessageType::Text,
+                text: Some(
+                    json!({
+                        "type": "budgets",
+                        "budgets": state.budgets,
+                    })
+                    .to_string(),
+                ),
+                data: None,
+            }],
+        },),
+    ))
+}
+
+fn handle_set_budgets(
+    state: &mut State,
+    budgets: Budgets,
+) -> Result<(Option<Vec<u8>>, (WebsocketResponse,)), String> {
+    if let Err(e) = state.set_budgets(budgets) {
+        log(&format!("Failed to set budgets: {}", e));
+        return Ok((
+            Some(serde_json::to_vec(state).unwrap()),
+            (create_error_response(format!(
+                "Failed to set budgets: {}",
+                e
+            )),),
+        ));
+    }
+
+    handle_get_budgets(state)
+}
+
 fn default_response(state: &State) -> Result<(Option<Vec<u8>>, (WebsocketResponse,)), String> {
     Ok((
         Some(serde_json::to_vec(state).unwrap()),
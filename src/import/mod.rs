@@ -0,0 +1,301 @@
+use crate::api::message_id;
+use crate::messages::{AssistantMessage, Message, UserMessage};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+// Model recorded on imported replies when the export does not name one
+const UNKNOWN_MODEL: &str = "unknown";
+
+/// Export formats conversations can be imported from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportFormat {
+    ChatGpt, // conversations.json from a ChatGPT data export
+    Claude,  // conversations.json from a Claude data export
+}
+
+impl ImportFormat {
+    pub fn parse(format: &str) -> Option<Self> {
+        match format.to_lowercase().as_str() {
+            "chatgpt" | "openai" => Some(ImportFormat::ChatGpt),
+            "claude" | "anthropic" => Some(ImportFormat::Claude),
+            _ => None,
+        }
+    }
+
+    /// Tell the formats apart by their conversation fields
+    pub fn detect(conversation: &Value) -> Option<Self> {
+        if conversation.get("mapping").is_some() {
+            Some(ImportFormat::ChatGpt)
+        } else if conversation.get("chat_messages").is_some() {
+            Some(ImportFormat::Claude)
+        } else {
+            None
+        }
+    }
+}
+
+/// A conversation read from an export, ready to be stored as a chain
+#[derive(Debug, Clone)]
+pub struct ImportedConversation {
+    pub name: String,
+    pub messages: Vec<ImportedMessage>, // Parents always come before their children
+    pub head: Option<String>,           // Source id of the message the chat should open at
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportedMessage {
+    pub source_id: String,
+    pub parent: Option<String>, // Source id of the nearest imported ancestor
+    pub message: Message,
+    pub timestamp: Option<u64>, // Milliseconds since the epoch
+}
+
+/// Read every conversation in an export. `body` may be the whole export (an array) or a
+/// single conversation, and `format` is detected per conversation when not given.
+pub fn parse(
+    body: &Value,
+    format: Option<ImportFormat>,
+) -> Result<Vec<ImportedConversation>, String> {
+    let conversations = match body {
+        Value::Array(conversations) => conversations.iter().collect(),
+        conversation => vec![conversation],
+    };
+
+    conversations
+        .into_iter()
+        .enumerate()
+        .map(
+            |(i, conversation)| match format.or_else(|| ImportFormat::detect(conversation)) {
+                Some(ImportFormat::ChatGpt) => parse_chatgpt(conversation),
+                Some(ImportFormat::Claude) => parse_claude(conversation),
+                None => Err(format!(
+                    "Conversation {} is not in a known export format",
+                    i
+                )),
+            },
+        )
+        .collect()
+}
+
+// ChatGPT keeps each conversation as a tree of nodes in `mapping`, and `current_node` is the
+// branch that was open. Hidden system turns, tool output and other non-text nodes are
+// skipped; their children are attached to the nearest imported ancestor instead.
+fn parse_chatgpt(conversation: &Value) -> Result<ImportedConversation, String> {
+    let mapping = conversation["mapping"]
+        .as_object()
+        .ok_or("ChatGPT conversation has no mapping")?;
+
+    let mut messages = Vec::new();
+    let mut imported = HashSet::new();
+    let mut pending: Vec<(String, Option<String>)> = mapping
+        .iter()
+        .filter(|(_, node)| {
+            !node["parent"]
+                .as_str()
+                .is_some_and(|parent| mapping.contains_key(parent))
+        })
+        .map(|(id, _)| (id.clone(), None))
+        .collect();
+
+    while let Some((node_id, parent)) = pending.pop() {
+        let node = &mapping[&node_id];
+        let message = chatgpt_message(&node["message"]);
+
+        let parent_for_children = match message {
+            Some(message) => {
+                messages.push(ImportedMessage {
+                    source_id: node_id.clone(),
+                    parent,
+                    message,
+                    timestamp: node["message"]["create_time"]
+                        .as_f64()
+                        .map(|seconds| (seconds * 1000.0) as u64),
+                });
+                imported.insert(node_id.clone());
+                Some(node_id.clone())
+            }
+            None => parent,
+        };
+
+        for child in node["children"].as_array().into_iter().flatten().rev() {
+            if let Some(child) = child.as_str().filter(|id| mapping.contains_key(*id)) {
+                pending.push((child.to_string(), parent_for_children.clone()));
+            }
+        }
+    }
+
+    // Open the chat where it was left, or on the nearest imported message above that
+    let mut head = conversation["current_node"].as_str().map(String::from);
+    while let Some(id) = head.clone().filter(|id| !imported.contains(id)) {
+        head = mapping
+            .get(&id)
+            .and_then(|node| node["parent"].as_str())
+            .map(String::from);
+    }
+
+    Ok(ImportedConversation {
+        name: conversation["title"]
+            .as_str()
+            .filter(|title| !title.trim().is_empty())
+            .unwrap_or("Imported chat")
+            .to_string(),
+        head: head.or_else(|| messages.last().map(|m| m.source_id.clone())),
+        messages,
+    })
+}
+
+fn chatgpt_message(message: &Value) -> Option<Message> {
+    let content_type = message["content"]["content_type"].as_str()?;
+    if content_type != "text" && content_type != "multimodal_text" {
+        return None;
+    }
+
+    // Multimodal parts mix text with image references; only the text is kept
+    let text = message["content"]["parts"]
+        .as_array()?
+        .iter()
+        .filter_map(Value::as_str)
+        .collect::<Vec<_>>()
+        .join("\n");
+    if text.trim().is_empty() {
+        return None;
+    }
+
+    match message["author"]["role"].as_str()? {
+        "user" => Some(Message::User(UserMessage { content: text })),
+        "assistant" => Some(imported_reply(
+            text,
+            message["metadata"]["model_slug"].as_str(),
+        )),
+        _ => None,
+    }
+}
+
+// Claude lists messages oldest first. Newer exports link each message to its parent, which
+// keeps edits and retries as branches; older ones are a single thread.
+fn parse_claude(conversation: &Value) -> Result<ImportedConversation, String> {
+    let chat_messages = conversation["chat_messages"]
+        .as_array()
+        .ok_or("Claude conversation has no chat_messages")?;
+
+    let mut messages: Vec<ImportedMessage> = Vec::new();
+    // Nearest imported message for every source id seen so far, skipped ones included
+    let mut resolved: HashMap<String, Option<String>> = HashMap::new();
+    let mut previous: Option<String> = None;
+    for (i, chat_message) in chat_messages.iter().enumerate() {
+        let source_id = chat_message["uuid"]
+            .as_str()
+            .map(String::from)
+            .unwrap_or_else(|| i.to_string());
+        let parent = match chat_message["parent_message_uuid"].as_str() {
+            Some(parent) => resolved.get(parent).cloned().flatten(),
+            None => previous.clone(),
+        };
+
+        let text = match chat_message["text"]
+            .as_str()
+            .filter(|t| !t.trim().is_empty())
+        {
+            Some(text) => text.to_string(),
+            None => chat_message["content"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter(|block| block["type"] == "text")
+                .filter_map(|block| block["text"].as_str())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        };
+        let message = match chat_message["sender"].as_str() {
+            _ if text.trim().is_empty() => None,
+            Some("human") => Some(Message::User(UserMessage { content: text })),
+            Some("assistant") => Some(imported_reply(text, chat_message["model"].as_str())),
+            _ => None,
+        };
+        let message = match message {
+            Some(message) => message,
+            None => {
+                resolved.insert(source_id, parent);
+                continue;
+            }
+        };
+
+        resolved.insert(source_id.clone(), Some(source_id.clone()));
+        previous = Some(source_id.clone());
+        messages.push(ImportedMessage {
+            source_id,
+            parent,
+            message,
+            timestamp: chat_message["created_at"]
+                .as_str()
+                .and_then(parse_timestamp),
+        });
+    }
+
+    Ok(ImportedConversation {
+        name: conversation["name"]
+            .as_str()
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or("Imported chat")
+            .to_string(),
+        head: previous,
+        messages,
+    })
+}
+
+fn imported_reply(content: String, model: Option<&str>) -> Message {
+    Message::Assistant(AssistantMessage {
+        id: message_id(&content),
+        content,
+        model: model.unwrap_or(UNKNOWN_MODEL).to_string(),
+        finish_reason: "stop".to_string(),
+        native_finish_reason: None,
+        usage: Default::default(),
+        input_cost_per_million_tokens: None,
+        output_cost_per_million_tokens: None,
+        tool_calls: None,
+        continuation_of: None,
+        truncated: false,
+        error: None,
+        requested_model: None,
+    })
+}
+
+// Milliseconds since the epoch for an RFC 3339 timestamp such as 2024-03-01T12:34:56.789Z
+fn parse_timestamp(timestamp: &str) -> Option<u64> {
+    let (date, time) = timestamp.split_once('T')?;
+    let mut date_parts = date.splitn(3, '-').map(|part| part.parse::<i64>());
+    let (year, month, day) = (
+        date_parts.next()?.ok()?,
+        date_parts.next()?.ok()?,
+        date_parts.next()?.ok()?,
+    );
+
+    // Split off the zone: Z, +HH:MM or -HH:MM
+    let zone_start = time.find(['Z', '+', '-']).unwrap_or(time.len());
+    let (clock, zone) = time.split_at(zone_start);
+    let offset_minutes = match zone.split_once(':') {
+        Some((hours, minutes)) => {
+            let sign = if hours.starts_with('-') { -1 } else { 1 };
+            sign * (hours[1..].parse::<i64>().ok()? * 60 + minutes.parse::<i64>().ok()?)
+        }
+        None => 0,
+    };
+
+    let mut clock_parts = clock.splitn(3, ':');
+    let hours: i64 = clock_parts.next()?.parse().ok()?;
+    let minutes: i64 = clock_parts.next()?.parse().ok()?;
+    let seconds: f64 = clock_parts.next().unwrap_or("0").parse().ok()?;
+
+    // Howard Hinnant's days-from-civil algorithm
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    let millis = (days * 86_400 + hours * 3600 + (minutes - offset_minutes) * 60) * 1000
+        + (seconds * 1000.0) as i64;
+    u64::try_from(millis).ok()
+}
@@ -12,14 +12,24 @@
 //!
 
 mod api;
+mod backup;
 mod bindings;
+mod context;
+mod export;
 mod fs;
+mod gc;
 mod handlers;
+mod import;
 mod mcp_server;
 mod messages;
 mod resources;
 mod state;
+mod titles;
+mod usage;
 
+use api::openai_compatible::OpenAiCompatibleConfig;
+use api::retry::RetryConfig;
+use api::Providers;
 use bindings::exports::ntwk::theater::actor::Guest as ActorGuest;
 use bindings::exports::ntwk::theater::http_handlers::Guest as HttpHandlersGuest;
 use bindings::exports::ntwk::theater::message_server_client::Guest as MessageServerClientGuest;
@@ -34,9 +44,17 @@ use bindings::ntwk::theater::message_server_host::send_on_channel;
 use bindings::ntwk::theater::runtime::log;
 use bindings::ntwk::theater::store;
 use bindings::ntwk::theater::websocket_types::{MessageType, WebsocketMessage};
+use context::summary::SummarizationConfig;
+use context::ContextStrategy;
 use mcp_server::McpServerConfig;
+use messages::cache::DEFAULT_CACHE_CAPACITY;
 use messages::ModelInfo;
-use state::State;
+use state::{
+    GenerationSettings, State, StoreSettings, DEFAULT_MAX_CONTINUATION_ROUNDS,
+    DEFAULT_TRASH_RETENTION_DAYS,
+};
+use titles::TitlingConfig;
+use usage::budget::Budgets;
 
 use serde::{Deserialize, Serialize};
 
@@ -44,10 +62,20 @@ use serde::{Deserialize, Serialize};
 struct InitData {
     store_id: Option<String>,
     openrouter_api_key: String,
+    anthropic_api_key: Option<String>,
+    openai_compatible: Option<OpenAiCompatibleConfig>,
     model_configs: Vec<ModelInfo>,
     assets_store_id: Option<String>,
     assets_runtime_content_fs: Option<String>,
     mcp_server_configs: Option<Vec<McpServerConfig>>,
+    max_continuation_rounds: Option<u32>,
+    retry: Option<RetryConfig>,
+    budgets: Option<Budgets>,
+    context_strategy: Option<ContextStrategy>,
+    summarization: Option<SummarizationConfig>,
+    titling: Option<TitlingConfig>,
+    trash_retention_days: Option<u32>,
+    cache_capacity: Option<usize>,
 }
 
 struct Component;
@@ -83,6 +111,25 @@ fn setup_http_server() -> Result<u64, String> {
 
     // Message API routes
     add_route(server_id, "/api/messages", "GET", api_handler_id)?;
+    add_route(
+        server_id,
+        "/api/messages/{id}/branches",
+        "GET",
+        api_handler_id,
+    )?;
+    add_route(server_id, "/api/messages/{id}/edit", "POST", api_handler_id)?;
+    add_route(
+        server_id,
+        "/api/messages/{id}/regenerate",
+        "POST",
+        api_handler_id,
+    )?;
+    add_route(
+        server_id,
+        "/api/messages/{id}/checkout",
+        "POST",
+        api_handler_id,
+    )?;
 
     // Chat API routes
     add_route(server_id, "/api/chats", "GET", api_handler_id)?;
@@ -90,6 +137,33 @@ fn setup_http_server() -> Result<u64, String> {
     add_route(server_id, "/api/chats/{id}", "GET", api_handler_id)?;
     add_route(server_id, "/api/chats/{id}", "PUT", api_handler_id)?;
     add_route(server_id, "/api/chats/{id}", "DELETE", api_handler_id)?;
+    add_route(server_id, "/api/chats/{id}/fork", "POST", api_handler_id)?;
+    add_route(server_id, "/api/chats/{id}/usage", "GET", api_handler_id)?;
+    add_route(server_id, "/api/chats/{id}/export", "GET", api_handler_id)?;
+    add_route(
+        server_id,
+        "/api/chats/{id}/summarize",
+        "POST",
+        api_handler_id,
+    )?;
+    add_route(server_id, "/api/usage", "GET", api_handler_id)?;
+    add_route(server_id, "/api/budgets", "GET", api_handler_id)?;
+    add_route(server_id, "/api/search", "GET", api_handler_id)?;
+    add_route(server_id, "/api/import", "POST", api_handler_id)?;
+    add_route(server_id, "/api/backup", "GET", api_handler_id)?;
+    add_route(server_id, "/api/restore", "POST", api_handler_id)?;
+    add_route(server_id, "/api/trash", "GET", api_handler_id)?;
+    add_route(server_id, "/api/admin/gc", "GET", api_handler_id)?;
+    add_route(server_id, "/api/admin/gc", "POST", api_handler_id)?;
+    add_route(server_id, "/api/admin/cache", "GET", api_handler_id)?;
+    add_route(server_id, "/api/trash", "DELETE", api_handler_id)?;
+    add_route(server_id, "/api/trash/{id}", "DELETE", api_handler_id)?;
+    add_route(server_id, "/api/trash/{id}/restore", "POST", api_handler_id)?;
+    add_route(server_id, "/api/budgets", "PUT", api_handler_id)?;
+    add_route(server_id, "/api/prompts", "GET", api_handler_id)?;
+    add_route(server_id, "/api/prompts", "POST", api_handler_id)?;
+    add_route(server_id, "/api/prompts/{name}", "GET", api_handler_id)?;
+    add_route(server_id, "/api/prompts/{name}", "DELETE", api_handler_id)?;
 
     // Enable WebSocket
     enable_websocket(
@@ -149,14 +223,37 @@ impl ActorGuest for Component {
             openrouter_key_preview
         ));
 
+        // Set up a client for each configured provider
+        let providers = Providers::new(
+            init_data.openrouter_api_key,
+            init_data.anthropic_api_key,
+            init_data.openai_compatible,
+            init_data.model_configs,
+        );
+
         // Initialize state with all API keys
         let initial_state = State::new(
             id,
             store_id,
-            init_data.openrouter_api_key, // Pass the OpenRouter API key
+            providers,
             server_id,
             init_data.mcp_server_configs,
-            init_data.model_configs,
+            GenerationSettings {
+                max_continuation_rounds: init_data
+                    .max_continuation_rounds
+                    .unwrap_or(DEFAULT_MAX_CONTINUATION_ROUNDS),
+                retry_config: init_data.retry.unwrap_or_default(),
+                budgets: init_data.budgets.unwrap_or_default(),
+                context_strategy: init_data.context_strategy.unwrap_or_default(),
+                summarization: init_data.summarization,
+                titling: init_data.titling,
+            },
+            StoreSettings {
+                trash_retention_days: init_data
+                    .trash_retention_days
+                    .unwrap_or(DEFAULT_TRASH_RETENTION_DAYS),
+                cache_capacity: init_data.cache_capacity.unwrap_or(DEFAULT_CACHE_CAPACITY),
+            },
         );
 
         log("State initialized");
@@ -270,18 +367,10 @@ impl HttpHandlersGuest for Component {
             }
 
             // Also send the list of available chats
-            let mut chats = Vec::new();
-            if let Ok(chat_ids) = current_state.store.list_chat_ids() {
-                for chat_id in chat_ids {
-                    if let Ok(Some(chat_info)) = current_state.store.get_chat_info(&chat_id) {
-                        chats.push(serde_json::json!({
-                            "id": chat_info.id,
-                            "name": chat_info.name,
-                            "icon": chat_info.icon
-                        }));
-                    }
-                }
-            }
+            let chats = current_state
+                .store
+                .list_chat_summaries()
+                .unwrap_or_default();
 
             let chats_message = WebsocketMessage {
                 ty: MessageType::Text,
@@ -1,36 +1,229 @@
+use crate::bindings::ntwk::theater::message_server_host::{request, send};
+use crate::bindings::ntwk::theater::runtime::log;
 use crate::bindings::ntwk::theater::supervisor::spawn;
+use mcp_protocol::types::tool::Tool;
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+// MCP protocol revision we advertise during the initialize handshake
+const MCP_PROTOCOL_VERSION: &str = "2024-11-05";
+
+// Translator spawned for configs from before `manifest_path` could be set
+const DEFAULT_TRANSLATOR_MANIFEST: &str = "/Users/colinrozzi/work/actors/mcp-poc/manifest.toml";
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct McpServerConfig {
-    server_path: String,
-    args: Vec<String>,
+    pub name: Option<String>, // Display name, defaults to the server path
+    #[serde(default)]
+    pub manifest_path: Option<String>, // Manifest of the translator actor that speaks to the server
+    pub server_path: String,  // MCP server binary launched by the translator
+    pub args: Vec<String>,
 }
 
+/// Client side of a single MCP server, reached through a translator actor over the message server
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct McpServer {
     config: McpServerConfig,
     translator_id: Option<String>,
+    next_request_id: u64,
+    pub server_info: Option<Value>,
+    pub tools: Vec<Tool>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct ToolConfig {}
-
 impl McpServer {
     pub fn new(config: McpServerConfig) -> Self {
         McpServer {
             config,
             translator_id: None,
+            next_request_id: 1,
+            server_info: None,
+            tools: Vec::new(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        self.config
+            .name
+            .as_deref()
+            .unwrap_or(&self.config.server_path)
+    }
+
+    fn manifest_path(&self) -> &str {
+        self.config
+            .manifest_path
+            .as_deref()
+            .unwrap_or(DEFAULT_TRANSLATOR_MANIFEST)
+    }
+
+    /// Spawn the translator, perform the initialize handshake and discover the server's tools
+    pub fn start(&mut self) -> Result<(), String> {
+        log(&format!(
+            "Starting MCP server {} via translator {}",
+            self.name(),
+            self.manifest_path()
+        ));
+
+        let init_bytes = serde_json::to_vec(&json!({
+            "server_path": self.config.server_path,
+            "args": self.config.args,
+        }))
+        .map_err(|e| e.to_string())?;
+
+        let translator_id = spawn(self.manifest_path(), Some(&init_bytes))?;
+        log(&format!(
+            "Spawned MCP translator {} for {}",
+            translator_id,
+            self.name()
+        ));
+        self.translator_id = Some(translator_id);
+
+        self.initialize()?;
+        self.refresh_tools()?;
+
+        log(&format!(
+            "MCP server {} ready with {} tools",
+            self.name(),
+            self.tools.len()
+        ));
+        Ok(())
+    }
+
+    fn initialize(&mut self) -> Result<(), String> {
+        let result = self.rpc(
+            "initialize",
+            json!({
+                "protocolVersion": MCP_PROTOCOL_VERSION,
+                "capabilities": {},
+                "clientInfo": {
+                    "name": "chat",
+                    "version": env!("CARGO_PKG_VERSION"),
+                }
+            }),
+        )?;
+
+        self.server_info = result.get("serverInfo").cloned();
+        log(&format!(
+            "MCP server {} initialized: {:?}",
+            self.name(),
+            self.server_info
+        ));
+
+        // Tell the server we are ready for normal operation
+        self.notify("notifications/initialized", json!({}))
+    }
+
+    /// Re-run `tools/list`, following pagination cursors, and cache the result
+    pub fn refresh_tools(&mut self) -> Result<(), String> {
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let params = match &cursor {
+                Some(cursor) => json!({ "cursor": cursor }),
+                None => json!({}),
+            };
+            let result = self.rpc("tools/list", params)?;
+
+            let page: Vec<Tool> =
+                serde_json::from_value(result.get("tools").cloned().unwrap_or(json!([])))
+                    .map_err(|e| format!("Invalid tools/list response: {}", e))?;
+            tools.extend(page);
+
+            cursor = result
+                .get("nextCursor")
+                .and_then(|c| c.as_str())
+                .map(String::from);
+            if cursor.is_none() {
+                break;
+            }
         }
+
+        self.tools = tools;
+        Ok(())
+    }
+
+    /// Whether this server advertises a tool with the given name
+    pub fn has_tool(&self, name: &str) -> bool {
+        self.tools.iter().any(|tool| tool.name == name)
     }
 
-    pub fn start(&mut self) {
-        let mcp_translator = spawn(
-            "/Users/colinrozzi/work/actors/mcp-poc/manifest.toml",
-            Some(&serde_json::to_vec(&self.config).unwrap()),
-        )
-        .unwrap();
+    /// Invoke a tool and flatten its content blocks into the text handed back to the model
+    pub fn call_tool(&mut self, name: &str, arguments: Value) -> Result<String, String> {
+        log(&format!(
+            "Calling tool {} on MCP server {}",
+            name,
+            self.name()
+        ));
+        let result = self.rpc(
+            "tools/call",
+            json!({
+                "name": name,
+                "arguments": arguments,
+            }),
+        )?;
+
+        let text = result
+            .get("content")
+            .and_then(|c| c.as_array())
+            .map(|blocks| {
+                blocks
+                    .iter()
+                    .map(|block| match block.get("text").and_then(|t| t.as_str()) {
+                        Some(text) => text.to_string(),
+                        None => block.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .unwrap_or_else(|| result.to_string());
+
+        if result.get("isError").and_then(|e| e.as_bool()) == Some(true) {
+            return Err(text);
+        }
+
+        Ok(text)
+    }
+
+    // Send a JSON-RPC request to the translator and return its `result`
+    fn rpc(&mut self, method: &str, params: Value) -> Result<Value, String> {
+        let translator_id = self
+            .translator_id
+            .clone()
+            .ok_or_else(|| format!("MCP server {} has not been started", self.name()))?;
+
+        let id = self.next_request_id;
+        self.next_request_id += 1;
+
+        let message = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        });
+
+        let response = request(&translator_id, &serde_json::to_vec(&message).unwrap())?;
+        let response: Value = serde_json::from_slice(&response)
+            .map_err(|e| format!("Invalid response from MCP server: {}", e))?;
+
+        if let Some(error) = response.get("error") {
+            return Err(format!("MCP {} failed: {}", method, error));
+        }
+
+        Ok(response.get("result").cloned().unwrap_or(Value::Null))
+    }
+
+    fn notify(&self, method: &str, params: Value) -> Result<(), String> {
+        let translator_id = self
+            .translator_id
+            .as_ref()
+            .ok_or_else(|| format!("MCP server {} has not been started", self.name()))?;
+
+        let message = json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+        });
 
-        self.translator_id = Some(mcp_translator);
+        send(translator_id, &serde_json::to_vec(&message).unwrap())
     }
 }
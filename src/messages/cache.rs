@@ -0,0 +1,111 @@
+use crate::messages::ChainEntry;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+pub const DEFAULT_CACHE_CAPACITY: usize = 1024;
+
+/// Lookup counts, kept in actor state so they add up across requests
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CacheMetrics {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+impl CacheMetrics {
+    /// Share of lookups served from the cache, 0 before the first lookup
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+/// Least-recently-used cache of chain entries, bounded to `capacity` entries.
+///
+/// Only the capacity and metrics are serialized. The entries never become part of actor
+/// state, so the cache starts empty with every handler call and only saves repeated loads
+/// within one request.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct EntryCache {
+    capacity: usize,
+    pub metrics: CacheMetrics,
+    #[serde(skip)]
+    entries: HashMap<String, (ChainEntry, u64)>, // Entry and when it was last used
+    #[serde(skip)]
+    recency: BTreeMap<u64, String>, // Last use -> entry ID, least recent first
+    #[serde(skip)]
+    clock: u64,
+}
+
+impl Default for EntryCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CACHE_CAPACITY)
+    }
+}
+
+impl EntryCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            metrics: CacheMetrics::default(),
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            clock: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Look an entry up, counting the hit or miss and marking it as recently used
+    pub fn get(&mut self, id: &str) -> Option<ChainEntry> {
+        let tick = self.tick();
+        match self.entries.get_mut(id) {
+            Some((entry, last_used)) => {
+                self.recency.remove(last_used);
+                *last_used = tick;
+                self.recency.insert(tick, id.to_string());
+                self.metrics.hits += 1;
+                Some(entry.clone())
+            }
+            None => {
+                self.metrics.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Add or refresh an entry, evicting the least recently used one when full
+    pub fn insert(&mut self, id: String, entry: ChainEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let tick = self.tick();
+        if let Some((_, last_used)) = self.entries.insert(id.clone(), (entry, tick)) {
+            self.recency.remove(&last_used);
+        }
+        self.recency.insert(tick, id);
+
+        while self.entries.len() > self.capacity {
+            match self.recency.pop_first() {
+                Some((_, oldest)) => {
+                    self.entries.remove(&oldest);
+                    self.metrics.evictions += 1;
+                }
+                None => break,
+            }
+        }
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+}
@@ -1,6 +1,12 @@
+pub mod anthropic;
+pub mod cache;
 pub mod openrouter;
+pub mod search;
 pub mod store;
+pub mod tokens;
 
+use crate::api::error::GenerationError;
+use crate::context::ContextStrategy;
 use openrouter::OpenRouterUsage;
 use serde::{Deserialize, Serialize};
 
@@ -9,20 +15,39 @@ pub struct ChainEntry {
     pub parents: Vec<String>,
     pub id: Option<String>,
     pub data: MessageData,
+    #[serde(default)]
+    pub timestamp: Option<u64>, // Milliseconds since the epoch; unset on older entries
 }
 
+// Nearly every entry is a chat message, so boxing it would only add indirection
+#[allow(clippy::large_enum_variant)]
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum MessageData {
     Chat(Message),
+    /// A condensed version of earlier entries, sent in their place
+    Summary {
+        covers: Vec<String>, // Entries the summary stands in for
+        text: String,
+        model: String, // Model that wrote the summary
+    },
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum Message {
+    System(SystemMessage),
     User(UserMessage),
     Assistant(AssistantMessage),
     Tool(ToolMessage),
 }
 
+/// Instructions for the model: the chat's system prompt, or those of an internal request
+/// such as titling. Providers may hoist these out of the conversation, so content that has
+/// a place in it, like a stored summary, is sent as a user turn instead.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SystemMessage {
+    pub content: String,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct UserMessage {
     pub content: String,
@@ -38,6 +63,20 @@ pub struct AssistantMessage {
     pub usage: OpenRouterUsage,
     pub input_cost_per_million_tokens: Option<f64>,
     pub output_cost_per_million_tokens: Option<f64>,
+    pub tool_calls: Option<Vec<ToolCall>>,
+    pub continuation_of: Option<String>, // Entry this piece continues after a "length" stop
+    #[serde(default)]
+    pub truncated: bool, // Hit the length limit and was not continued further
+    pub error: Option<GenerationError>,  // Set on failed or filtered turns
+    pub requested_model: Option<String>, // Model asked for, when a fallback model answered
+}
+
+/// A tool invocation requested by the model as part of an assistant turn
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    pub arguments: String, // JSON-encoded arguments, as produced by the model
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -51,16 +90,88 @@ pub struct ModelInfo {
     pub id: String,
     pub display_name: String,
     pub max_tokens: u32,
+    pub context_window: Option<u32>, // Prompt plus reply, in tokens; max_tokens when unset
     pub provider: Option<String>,
     pub tools_enabled: bool,
     pub input_cost_per_million_tokens: Option<f64>,
     pub output_cost_per_million_tokens: Option<f64>,
+    pub generation_params: Option<GenerationParams>, // Defaults for every request to this model
+    pub fallback_models: Option<Vec<String>>, // Tried in order when this model is unavailable
+}
+
+/// Sampling settings sent with a completion request. Unset fields fall back to the next
+/// layer down: per-call overrides, then the chat's settings, then the model's defaults.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct GenerationParams {
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+    pub top_p: Option<f32>,
+    pub stop: Option<Vec<String>>,
+}
+
+impl GenerationParams {
+    /// Layer `overrides` on top of these params, keeping our values where it leaves a gap
+    pub fn merged_with(&self, overrides: &GenerationParams) -> GenerationParams {
+        GenerationParams {
+            temperature: overrides.temperature.or(self.temperature),
+            max_tokens: overrides.max_tokens.or(self.max_tokens),
+            top_p: overrides.top_p.or(self.top_p),
+            stop: overrides.stop.clone().or_else(|| self.stop.clone()),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ChatInfo {
-    pub id: String,           // Unique identifier (same as the label)
-    pub name: String,         // Display name
-    pub head: Option<String>, // Head message ContentRef
-    pub icon: Option<String>, // Optional icon identifier
+    pub id: String,                      // Unique identifier (same as the label)
+    pub name: String,                    // Display name
+    pub head: Option<String>,            // Head message ContentRef
+    pub icon: Option<String>,            // Optional icon identifier
+    pub forked_from: Option<ForkOrigin>, // Where this chat branched off another chat
+    pub system_prompt: Option<String>,   // Sent as the system message for every request
+    pub generation_params: Option<GenerationParams>, // Overrides the model's defaults
+    pub context_strategy: Option<ContextStrategy>, // Overrides the configured strategy
+    #[serde(default)]
+    pub pinned_messages: Vec<String>, // Kept in context by the keep_pinned strategy
+    #[serde(default)]
+    pub title_locked: bool, // Never auto-titled: renamed, opted out, or titled already
+    #[serde(default)]
+    pub created_at: Option<u64>, // Milliseconds since the epoch; unknown for older chats
+}
+
+/// A chat as listed in the chat index, everything a chat list needs in one document
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ChatSummary {
+    pub id: String,
+    pub name: String,
+    pub icon: Option<String>,
+    pub forked_from: Option<ForkOrigin>,
+    pub head: Option<String>,
+    pub created_at: Option<u64>,    // Milliseconds since the epoch
+    pub updated_at: Option<u64>,    // When the head entry was written
+    pub message_count: u64,         // Entries in the conversation up to the head
+    pub last_model: Option<String>, // Model of the latest reply
+}
+
+/// A deleted chat waiting in the trash. Its info and messages stay in the store until
+/// it is purged.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TrashedChat {
+    pub id: String,
+    pub name: String,
+    pub head: Option<String>,
+    pub deleted_at: u64, // Milliseconds since the epoch
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ForkOrigin {
+    pub chat_id: String,    // Chat the fork was taken from
+    pub message_id: String, // Message the fork starts at
+}
+
+/// A named, reusable system prompt from the prompt library
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SystemPrompt {
+    pub name: String,
+    pub content: String,
 }
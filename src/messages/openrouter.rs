@@ -1,3 +1,4 @@
+use crate::messages::ToolCall;
 use crate::ModelInfo;
 use mcp_protocol::types::tool::Tool;
 use serde::{Deserialize, Serialize};
@@ -8,6 +9,8 @@ pub struct OpenRouterMessage {
     pub content: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_call_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<OpenRouterToolCall>>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -15,7 +18,81 @@ pub struct OpenRouterRequest {
     pub model: String,
     pub messages: Vec<OpenRouterMessage>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub tools: Option<Vec<Tool>>,
+    pub tools: Option<Vec<OpenRouterTool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop: Option<Vec<String>>,
+}
+
+// Tool definition in the OpenAI-compatible function calling format
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OpenRouterTool {
+    #[serde(rename = "type")]
+    pub tool_type: String,
+    pub function: OpenRouterFunction,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OpenRouterFunction {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub parameters: serde_json::Value,
+}
+
+impl From<&Tool> for OpenRouterTool {
+    fn from(tool: &Tool) -> Self {
+        Self {
+            tool_type: "function".to_string(),
+            function: OpenRouterFunction {
+                name: tool.name.clone(),
+                description: tool.description.clone(),
+                parameters: tool.input_schema.clone(),
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OpenRouterToolCall {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub call_type: String,
+    pub function: OpenRouterFunctionCall,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OpenRouterFunctionCall {
+    pub name: String,
+    pub arguments: String,
+}
+
+impl From<&OpenRouterToolCall> for ToolCall {
+    fn from(call: &OpenRouterToolCall) -> Self {
+        Self {
+            id: call.id.clone(),
+            name: call.function.name.clone(),
+            arguments: call.function.arguments.clone(),
+        }
+    }
+}
+
+impl From<&ToolCall> for OpenRouterToolCall {
+    fn from(call: &ToolCall) -> Self {
+        Self {
+            id: call.id.clone(),
+            call_type: "function".to_string(),
+            function: OpenRouterFunctionCall {
+                name: call.name.clone(),
+                arguments: call.arguments.clone(),
+            },
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -28,7 +105,8 @@ pub struct OpenRouterChoice {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct OpenRouterChoiceMessage {
     pub role: String,
-    pub content: String,
+    pub content: Option<String>, // null when the model only returns tool calls
+    pub tool_calls: Option<Vec<OpenRouterToolCall>>,
 }
 
 // OpenRouter client implementation
@@ -39,7 +117,7 @@ pub struct OpenRouterClient {
     model_configs: Vec<ModelInfo>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct OpenRouterUsage {
     pub prompt_tokens: u32,
     pub completion_tokens: u32,
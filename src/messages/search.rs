@@ -0,0 +1,167 @@
+use crate::messages::{Message, MessageData};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+// The index is split over many small documents so that indexing a message only rewrites the
+// postings of the terms it contains, its own document, and the totals
+pub const SEARCH_STATS_LABEL: &str = "search_stats";
+const TERM_LABEL_PREFIX: &str = "search_term_";
+const DOCUMENT_LABEL_PREFIX: &str = "search_doc_";
+
+// The single-document index used before it was split up
+pub const LEGACY_SEARCH_INDEX_LABEL: &str = "search_index";
+
+// Results returned by a search unless the client asks for a different number
+pub const DEFAULT_SEARCH_LIMIT: usize = 20;
+
+// BM25 parameters: term frequency saturation and document length normalization
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+// Snippets show this many characters, starting a little before the first match
+const SNIPPET_CHARS: usize = 160;
+const SNIPPET_LEAD_CHARS: usize = 60;
+
+/// Totals of the inverted index over the text of every user and assistant message. The
+/// postings of each term and each message's document are kept under their own labels.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SearchStats {
+    pub documents: u64,
+    pub total_terms: u64,
+}
+
+/// The messages containing a term, keyed by message id
+pub type Postings = BTreeMap<String, Posting>;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct Posting {
+    pub count: u32, // Occurrences of the term in the message
+    pub terms: u32, // Length of the message, for normalization
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IndexedDocument {
+    pub chat_ids: BTreeSet<String>, // Every chat the message was indexed for, as chats can share messages
+    pub role: String,
+    pub terms: u32,
+}
+
+/// A message matching a search
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SearchHit {
+    pub chat_id: String,
+    pub chat_ids: Vec<String>, // Every listed chat holding the message, starting with `chat_id`
+    pub message_id: String,
+    pub role: String,
+    pub snippet: String,
+    pub score: f64,
+}
+
+/// Label of the postings of a term
+pub fn term_label(term: &str) -> String {
+    format!("{}{}", TERM_LABEL_PREFIX, term)
+}
+
+/// Label of an indexed message's document
+pub fn document_label(message_id: &str) -> String {
+    format!("{}{}", DOCUMENT_LABEL_PREFIX, message_id)
+}
+
+/// How often each term occurs in a text, and how many terms it has
+pub fn term_counts(text: &str) -> (HashMap<String, u32>, u32) {
+    let terms = tokenize(text);
+    let mut counts: HashMap<String, u32> = HashMap::new();
+    for term in &terms {
+        *counts.entry(term.clone()).or_default() += 1;
+    }
+    (counts, terms.len() as u32)
+}
+
+/// The distinct terms of a query
+pub fn query_terms(query: &str) -> Vec<String> {
+    let mut terms = tokenize(query);
+    terms.sort();
+    terms.dedup();
+    terms
+}
+
+/// Score every message in the postings of the query's terms with BM25, best first
+pub fn rank(stats: &SearchStats, postings: &[Postings]) -> Vec<(String, f64)> {
+    let document_count = stats.documents as f64;
+    if document_count == 0.0 {
+        return Vec::new();
+    }
+    let average_terms = stats.total_terms as f64 / document_count;
+
+    let mut scores: HashMap<&str, f64> = HashMap::new();
+    for postings in postings {
+        let matching = postings.len() as f64;
+        let idf = (1.0 + (document_count - matching + 0.5) / (matching + 0.5)).ln();
+
+        for (message_id, posting) in postings {
+            let count = posting.count as f64;
+            let score = idf * count * (K1 + 1.0)
+                / (count + K1 * (1.0 - B + B * posting.terms as f64 / average_terms.max(1.0)));
+            *scores.entry(message_id).or_default() += score;
+        }
+    }
+
+    let mut results: Vec<(String, f64)> = scores
+        .into_iter()
+        .map(|(message_id, score)| (message_id.to_string(), score))
+        .collect();
+    results.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    results
+}
+
+/// Lowercased words of at least two characters
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() >= 2)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// The role and text of an entry that should be searchable
+pub fn searchable_text(data: &MessageData) -> Option<(&'static str, &str)> {
+    match data {
+        MessageData::Chat(Message::User(msg)) => Some(("user", &msg.content)),
+        MessageData::Chat(Message::Assistant(msg)) if msg.error.is_none() => {
+            Some(("assistant", &msg.content))
+        }
+        _ => None,
+    }
+}
+
+/// A short excerpt of `text` around the first word of the query it contains
+pub fn snippet(text: &str, query: &str) -> String {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let text = words.join(" ");
+    let query_terms = tokenize(query);
+
+    // Find the first word containing a query term, counted in characters
+    let mut position = 0;
+    let mut offset = 0;
+    for word in &words {
+        let word_lower = word.to_lowercase();
+        if query_terms
+            .iter()
+            .any(|term| word_lower.contains(term.as_str()))
+        {
+            position = offset;
+            break;
+        }
+        offset += word.chars().count() + 1;
+    }
+
+    let start = position.saturating_sub(SNIPPET_LEAD_CHARS);
+    let total = text.chars().count();
+    let mut snippet: String = text.chars().skip(start).take(SNIPPET_CHARS).collect();
+    if start > 0 {
+        snippet.insert(0, '…');
+    }
+    if start + SNIPPET_CHARS < total {
+        snippet.push('…');
+    }
+    snippet
+}
@@ -1,29 +1,55 @@
 use crate::bindings::ntwk::theater::runtime::log;
 use crate::bindings::ntwk::theater::store::{self, ContentRef};
-use crate::messages::{ChainEntry, ChatInfo};
+use crate::bindings::ntwk::theater::timing;
+use crate::messages::cache::{EntryCache, DEFAULT_CACHE_CAPACITY};
+use crate::messages::search::{
+    self, IndexedDocument, Posting, Postings, SearchHit, SearchStats, LEGACY_SEARCH_INDEX_LABEL,
+    SEARCH_STATS_LABEL,
+};
+use crate::messages::{
+    ChainEntry, ChatInfo, ChatSummary, Message, MessageData, SystemPrompt, TrashedChat,
+};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 
+const SYSTEM_PROMPTS_LABEL: &str = "system_prompts";
+const TRASH_LABEL: &str = "trash";
+// Kept out of the `chat_` namespace, where it would read as the info of a chat named "index"
+pub const CHAT_INDEX_LABEL: &str = "chats_index";
+const LEGACY_CHAT_INDEX_LABEL: &str = "chat_index";
+
+// Hex digits of the creation record's hash used as a chat ID
+const CHAT_ID_LENGTH: usize = 16;
+
 /// MessageStore implementation that uses the Theater runtime's built-in content-addressed store
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct MessageStore {
     pub store_id: String,
-    pub cache: HashMap<String, ChainEntry>,
+    #[serde(default)]
+    pub cache: EntryCache,
 }
 
 impl MessageStore {
     /// Create a new MessageStore with the given store ID
     pub fn new(store_id: String) -> Self {
+        Self::with_cache_capacity(store_id, DEFAULT_CACHE_CAPACITY)
+    }
+
+    /// Create a MessageStore that caches at most `capacity` loaded messages
+    pub fn with_cache_capacity(store_id: String, capacity: usize) -> Self {
         Self {
             store_id,
-            cache: HashMap::new(),
+            cache: EntryCache::new(capacity),
         }
     }
 
     /// Save a message to the store and return the updated entry with its ID
     pub fn save_message(
         &mut self,
-        mut entry: ChainEntry,
+        entry: ChainEntry,
         chat_id: &str,
     ) -> Result<ChainEntry, Box<dyn std::error::Error>> {
         log(&format!(
@@ -31,6 +57,217 @@ impl MessageStore {
             chat_id
         ));
 
+        let entry = self.store_entry(entry, chat_id)?;
+
+        // Move the chat head to the new entry
+        self.set_chat_head(chat_id, entry.id.clone())?;
+
+        // A stale search index is better than a lost message
+        if let Err(e) = self.index_for_search(&[(entry.clone(), chat_id.to_string())]) {
+            log(&format!(
+                "[ERROR] Failed to index message for search: {}",
+                e
+            ));
+        }
+
+        Ok(entry)
+    }
+
+    /// Add entries, each with the chat it belongs to, to the search index. Only the
+    /// postings of the terms they contain are rewritten, each once per call; for messages
+    /// already in the index only the chat is added to their document.
+    pub fn index_for_search(
+        &self,
+        entries: &[(ChainEntry, String)],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut stats: SearchStats = self.read_json(SEARCH_STATS_LABEL)?.unwrap_or_default();
+        let indexed_before = stats.documents;
+        let mut documents: HashMap<String, IndexedDocument> = HashMap::new();
+        let mut postings: HashMap<String, Postings> = HashMap::new();
+
+        for (entry, chat_id) in entries {
+            let (id, (role, text)) = match (&entry.id, search::searchable_text(&entry.data)) {
+                (Some(id), Some(searchable)) => (id, searchable),
+                _ => continue,
+            };
+            if let Some(document) = documents.get_mut(id) {
+                document.chat_ids.insert(chat_id.clone());
+                continue;
+            }
+            if let Some(mut document) =
+                self.read_json::<IndexedDocument>(&search::document_label(id))?
+            {
+                if document.chat_ids.insert(chat_id.clone()) {
+                    documents.insert(id.clone(), document);
+                }
+                continue;
+            }
+
+            let (counts, terms) = search::term_counts(text);
+            for (term, count) in counts {
+                let term_postings = match postings.entry(term) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => {
+                        let existing = self
+                            .read_json(&search::term_label(entry.key()))?
+                            .unwrap_or_default();
+                        entry.insert(existing)
+                    }
+                };
+                term_postings.insert(id.clone(), Posting { count, terms });
+            }
+
+            documents.insert(
+                id.clone(),
+                IndexedDocument {
+                    chat_ids: [chat_id.clone()].into(),
+                    role: role.to_string(),
+                    terms,
+                },
+            );
+            stats.documents += 1;
+            stats.total_terms += terms as u64;
+        }
+
+        if documents.is_empty() {
+            return Ok(());
+        }
+        for (term, term_postings) in &postings {
+            self.write_json(&search::term_label(term), term_postings)?;
+        }
+        for (id, document) in &documents {
+            self.write_json(&search::document_label(id), document)?;
+        }
+        if stats.documents == indexed_before {
+            return Ok(());
+        }
+        self.write_json(SEARCH_STATS_LABEL, &stats)
+    }
+
+    /// Search the text of every message, best match first. Messages of deleted chats are
+    /// left out, and `chat_id` narrows the search down to one chat.
+    pub fn search(
+        &mut self,
+        query: &str,
+        chat_id: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<SearchHit>, Box<dyn std::error::Error>> {
+        let stats: SearchStats = self.read_json(SEARCH_STATS_LABEL)?.unwrap_or_default();
+        let mut postings = Vec::new();
+        for term in search::query_terms(query) {
+            postings.extend(self.read_json::<Postings>(&search::term_label(&term))?);
+        }
+        let chat_ids = self.list_chat_ids()?;
+
+        let mut hits = Vec::new();
+        for (message_id, score) in search::rank(&stats, &postings) {
+            let document: IndexedDocument =
+                match self.read_json(&search::document_label(&message_id))? {
+                    Some(document) => document,
+                    None => continue,
+                };
+            // The message's chats that still exist, narrowed down to the one asked for
+            let live_chats: Vec<String> = document
+                .chat_ids
+                .iter()
+                .filter(|id| chat_ids.contains(id) && chat_id.is_none_or(|chat_id| chat_id == *id))
+                .cloned()
+                .collect();
+            let first_chat = match live_chats.first() {
+                Some(first_chat) => first_chat.clone(),
+                None => continue,
+            };
+
+            let entry = self.load_message(&message_id)?;
+            let snippet = search::searchable_text(&entry.data)
+                .map(|(_, text)| search::snippet(text, query))
+                .unwrap_or_default();
+            hits.push(SearchHit {
+                chat_id: first_chat,
+                chat_ids: live_chats,
+                message_id,
+                role: document.role.clone(),
+                snippet,
+                score,
+            });
+            if hits.len() >= limit {
+                break;
+            }
+        }
+
+        Ok(hits)
+    }
+
+    /// Index every message reachable from a chat head if there is no index yet, for stores
+    /// that predate search or kept the whole index in one document
+    pub fn ensure_search_index(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if store::get_by_label(&self.store_id, SEARCH_STATS_LABEL)?.is_some() {
+            return Ok(());
+        }
+        log("Building search index from existing chats");
+
+        let mut entries = Vec::new();
+        for chat_id in self.list_chat_ids()? {
+            let head = self
+                .get_chat_info(&chat_id)?
+                .and_then(|chat_info| chat_info.head);
+            for entry in self.history(head)? {
+                entries.push((entry, chat_id.clone()));
+            }
+        }
+
+        self.index_for_search(&entries)?;
+        // Even an empty store gets its totals, so the index is not rebuilt on every start
+        if store::get_by_label(&self.store_id, SEARCH_STATS_LABEL)?.is_none() {
+            self.write_json(SEARCH_STATS_LABEL, &SearchStats::default())?;
+        }
+        if store::get_by_label(&self.store_id, LEGACY_SEARCH_INDEX_LABEL)?.is_some() {
+            store::remove_label(&self.store_id, LEGACY_SEARCH_INDEX_LABEL)?;
+        }
+        log(&format!("Indexed {} messages for search", entries.len()));
+        Ok(())
+    }
+
+    /// Index every message reachable from `head` for a chat, such as a fork that starts
+    /// with another chat's history
+    pub fn index_history_for_search(
+        &mut self,
+        chat_id: &str,
+        head: Option<String>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let entries: Vec<(ChainEntry, String)> = self
+            .history(head)?
+            .into_iter()
+            .map(|entry| (entry, chat_id.to_string()))
+            .collect();
+        self.index_for_search(&entries)
+    }
+
+    // Every entry reachable from `head` through parents
+    fn history(
+        &mut self,
+        head: Option<String>,
+    ) -> Result<Vec<ChainEntry>, Box<dyn std::error::Error>> {
+        let mut entries = Vec::new();
+        let mut seen = std::collections::HashSet::new();
+        let mut pending: Vec<String> = head.into_iter().collect();
+        while let Some(id) = pending.pop() {
+            if !seen.insert(id.clone()) {
+                continue;
+            }
+            let entry = self.load_message(&id)?;
+            pending.extend(entry.parents.iter().cloned());
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+
+    /// Store a chain entry and record it in the children index without moving any chat head
+    pub fn store_entry(
+        &mut self,
+        mut entry: ChainEntry,
+        chat_id: &str,
+    ) -> Result<ChainEntry, Box<dyn std::error::Error>> {
         // Serialize the entry to bytes
         let content = serde_json::to_vec(&entry)?;
 
@@ -44,6 +281,27 @@ impl MessageStore {
         // Set the ID based on the content reference hash
         entry.id = Some(content_ref.hash.clone());
 
+        // Record the entry under each parent so alternative branches can be found later
+        if entry.parents.is_empty() {
+            self.add_to_index(&roots_label(chat_id), &content_ref.hash)?;
+        } else {
+            for parent in &entry.parents {
+                self.add_to_index(&children_label(chat_id, parent), &content_ref.hash)?;
+            }
+        }
+
+        // Update cache
+        self.cache.insert(content_ref.hash.clone(), entry.clone());
+
+        Ok(entry)
+    }
+
+    /// Point a chat's head at the given message
+    pub fn set_chat_head(
+        &self,
+        chat_id: &str,
+        head: Option<String>,
+    ) -> Result<ChatInfo, Box<dyn std::error::Error>> {
         // Get the current chat info
         let mut chat_info = self
             .get_chat_info(chat_id)?
@@ -51,18 +309,154 @@ impl MessageStore {
 
         // Update the chat head
         log(&format!(
-            "[DEBUG] Updating chat head from {:?} to {}",
-            chat_info.head, content_ref.hash
+            "[DEBUG] Updating chat head from {:?} to {:?}",
+            chat_info.head, head
         ));
-        chat_info.head = Some(content_ref.hash.clone());
+        chat_info.head = head;
 
         // Save the updated chat info
         self.update_chat_info(&chat_info)?;
 
-        // Update cache
-        self.cache.insert(content_ref.hash.clone(), entry.clone());
+        Ok(chat_info)
+    }
 
-        Ok(entry)
+    /// List the IDs of messages that have the given message as a parent in a chat, oldest
+    /// first. The same message can sit in several chats, each with its own branches.
+    pub fn list_children(
+        &self,
+        chat_id: &str,
+        id: &str,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        // Messages saved before the index was kept per chat come first
+        let mut children = self.read_index(&legacy_children_label(id))?;
+        for child in self.read_index(&children_label(chat_id, id))? {
+            if !children.contains(&child) {
+                children.push(child);
+            }
+        }
+        Ok(children)
+    }
+
+    /// List the alternatives at a message's position: every message sharing its parent
+    /// (or, for a first message, every first message of the chat), including itself
+    pub fn list_siblings(
+        &mut self,
+        id: &str,
+        chat_id: &str,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let entry = self.load_message(id)?;
+        let mut siblings = match entry.parents.first() {
+            Some(parent) => self.list_children(chat_id, parent)?,
+            None => {
+                // Roots recorded under the label's old name in the chat namespace come first
+                let mut roots = self.read_index(&legacy_roots_label(chat_id))?;
+                for root in self.read_index(&roots_label(chat_id))? {
+                    if !roots.contains(&root) {
+                        roots.push(root);
+                    }
+                }
+                roots
+            }
+        };
+
+        // Messages saved before the index existed are not listed under their parent
+        if !siblings.iter().any(|sibling| sibling == id) {
+            siblings.push(id.to_string());
+        }
+
+        Ok(siblings)
+    }
+
+    fn read_index(&self, label: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        Ok(self.read_json(label)?.unwrap_or_default())
+    }
+
+    /// Load a JSON document kept under a label, if the label exists
+    pub fn read_json<T: DeserializeOwned>(
+        &self,
+        label: &str,
+    ) -> Result<Option<T>, Box<dyn std::error::Error>> {
+        match store::get_by_label(&self.store_id, label)? {
+            Some(content_ref) => {
+                let content = store::get(&self.store_id, &content_ref)?;
+                Ok(Some(serde_json::from_slice(&content)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Store a JSON document and point the label at it
+    pub fn write_json<T: Serialize + ?Sized>(
+        &self,
+        label: &str,
+        value: &T,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let content_ref = store::store(&self.store_id, &serde_json::to_vec(value)?)?;
+        self.put_label(label, &content_ref)
+    }
+
+    fn add_to_index(&self, label: &str, id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let mut ids = self.read_index(label)?;
+        if ids.iter().any(|existing| existing == id) {
+            return Ok(());
+        }
+        ids.push(id.to_string());
+
+        self.write_json(label, &ids)
+    }
+
+    // Create the label, or move it if it already exists
+    fn put_label(
+        &self,
+        label: &str,
+        content_ref: &ContentRef,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if store::get_by_label(&self.store_id, label)?.is_some() {
+            store::replace_at_label(&self.store_id, label, content_ref)?;
+        } else {
+            store::label(&self.store_id, label, content_ref)?;
+        }
+        Ok(())
+    }
+
+    /// List the named system prompts in the prompt library
+    pub fn list_system_prompts(&self) -> Result<Vec<SystemPrompt>, Box<dyn std::error::Error>> {
+        Ok(self.read_json(SYSTEM_PROMPTS_LABEL)?.unwrap_or_default())
+    }
+
+    /// Look up a system prompt from the library by name
+    pub fn get_system_prompt(
+        &self,
+        name: &str,
+    ) -> Result<Option<SystemPrompt>, Box<dyn std::error::Error>> {
+        Ok(self
+            .list_system_prompts()?
+            .into_iter()
+            .find(|prompt| prompt.name == name))
+    }
+
+    /// Add a prompt to the library, replacing any existing prompt with the same name
+    pub fn save_system_prompt(
+        &self,
+        prompt: SystemPrompt,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut prompts = self.list_system_prompts()?;
+        match prompts.iter_mut().find(|p| p.name == prompt.name) {
+            Some(existing) => existing.content = prompt.content,
+            None => prompts.push(prompt),
+        }
+        self.write_json(SYSTEM_PROMPTS_LABEL, &prompts)
+    }
+
+    /// Remove a prompt from the library
+    pub fn delete_system_prompt(&self, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let mut prompts = self.list_system_prompts()?;
+        let count = prompts.len();
+        prompts.retain(|prompt| prompt.name != name);
+        if prompts.len() == count {
+            return Err(format!("System prompt {} not found", name).into());
+        }
+        self.write_json(SYSTEM_PROMPTS_LABEL, &prompts)
     }
 
     /// Save a message to the store with a specific ID (for committing pending child messages)
@@ -132,7 +526,7 @@ impl MessageStore {
         // Check cache first
         if let Some(msg) = self.cache.get(id) {
             log(&format!("[DEBUG] Message {} found in cache", id));
-            return Ok(msg.clone());
+            return Ok(msg);
         }
 
         log(&format!(
@@ -275,8 +669,8 @@ impl MessageStore {
     ) -> Result<ChatInfo, Box<dyn std::error::Error>> {
         log(&format!("Creating new chat: {}", name));
 
-        let num_chats = self.list_chat_ids()?.len();
-        let id = format!("{}", num_chats + 1);
+        let created_at = timing::now();
+        let id = self.new_chat_id(&name, created_at)?;
 
         // Create the chat info
         let chat_info = ChatInfo {
@@ -284,79 +678,228 @@ impl MessageStore {
             name,
             head: starting_head,
             icon: None,
+            forked_from: None,
+            system_prompt: None,
+            generation_params: None,
+            context_strategy: None,
+            pinned_messages: Vec::new(),
+            title_locked: false,
+            created_at: Some(created_at),
         };
 
-        // Try to store the chat info with enhanced error handling
-        match self.update_chat_info(&chat_info) {
-            Ok(_) => {
-                log(&format!("Chat info stored successfully for {}", id));
-
-                // Add the chat ID to the list of chats with safer operations
-                match self.list_chat_ids() {
-                    Ok(mut chat_ids) => {
-                        if !chat_ids.contains(&id) {
-                            chat_ids.push(id.clone());
-
-                            // Serialize with error handling
-                            match serde_json::to_vec(&chat_ids) {
-                                Ok(content) => {
-                                    // Store content with error handling
-                                    match store::store(&self.store_id, &content) {
-                                        Ok(content_ref) => {
-                                            // Update chats label with separate try blocks
-                                            log("Updating chats label");
-                                            let label_result = if let Ok(Some(_)) =
-                                                store::get_by_label(&self.store_id, "chats")
-                                            {
-                                                log("Replacing existing chats label");
-                                                store::replace_at_label(
-                                                    &self.store_id,
-                                                    "chats",
-                                                    &content_ref,
-                                                )
-                                            } else {
-                                                log("Creating new chats label");
-                                                store::label(&self.store_id, "chats", &content_ref)
-                                            };
-
-                                            if let Err(e) = label_result {
-                                                log(&format!(
-                                                    "Warning: Failed to update chats label: {}",
-                                                    e
-                                                ));
-                                                // Continue anyway since the chat itself was created
-                                            }
-                                        }
-                                        Err(e) => {
-                                            log(&format!(
-                                                "Warning: Failed to store chat IDs: {}",
-                                                e
-                                            ));
-                                            // Continue anyway since the chat itself was created
-                                        }
-                                    }
-                                }
-                                Err(e) => {
-                                    log(&format!("Warning: Failed to serialize chat IDs: {}", e));
-                                    // Continue anyway since the chat itself was created
-                                }
-                            }
-                        }
+        // The info goes in first: if the list update then fails, the chat is unlisted
+        // rather than listed without any info
+        self.update_chat_info(&chat_info)?;
+        self.update_chat_ids(|chat_ids| {
+            if !chat_ids.contains(&id) {
+                chat_ids.push(id.clone());
+            }
+        })?;
+
+        log(&format!("Created chat with ID: {}", id));
+        Ok(chat_info)
+    }
+
+    // Derive an ID from a hash of the chat's creation record, skipping IDs that still have
+    // a chat label (listed or trashed chats). Purging a chat removes its label, but only a
+    // record with the same name and creation time would hash to its ID again.
+    fn new_chat_id(
+        &self,
+        name: &str,
+        created_at: u64,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let mut attempt: u32 = 0;
+        loop {
+            let record = serde_json::to_string(&serde_json::json!({
+                "name": name,
+                "created_at": created_at,
+                "attempt": attempt,
+            }))?;
+            let mut hasher = Sha1::new();
+            hasher.update(record.as_bytes());
+            let id = format!("{:x}", hasher.finalize())[..CHAT_ID_LENGTH].to_string();
+
+            if store::get_by_label(&self.store_id, &format!("chat_{}", id))?.is_none() {
+                return Ok(id);
+            }
+            log(&format!("Chat ID {} is taken, trying another", id));
+            attempt += 1;
+        }
+    }
+
+    // Change the chat list and move the `chats` label to the result in one step, so a
+    // failed store call leaves the previous list in place
+    fn update_chat_ids(
+        &self,
+        change: impl FnOnce(&mut Vec<String>),
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let mut chat_ids = self.list_chat_ids()?;
+        change(&mut chat_ids);
+
+        let content_ref = store::store(&self.store_id, &serde_json::to_vec(&chat_ids)?)?;
+        self.put_label("chats", &content_ref)?;
+
+        if let Err(e) = self.sync_chat_index(&chat_ids) {
+            log(&format!("[ERROR] Failed to update chat index: {}", e));
+        }
+        Ok(chat_ids)
+    }
+
+    /// Summaries of every listed chat, in list order, read from the chat index
+    pub fn list_chat_summaries(&self) -> Result<Vec<ChatSummary>, Box<dyn std::error::Error>> {
+        match self.read_json(CHAT_INDEX_LABEL)? {
+            Some(index) => Ok(index),
+            None => self.sync_chat_index(&self.list_chat_ids()?),
+        }
+    }
+
+    /// Build the chat index if the store doesn't have one yet
+    pub fn ensure_chat_index(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if store::get_by_label(&self.store_id, CHAT_INDEX_LABEL)?.is_none() {
+            let index = self.sync_chat_index(&self.list_chat_ids()?)?;
+            log(&format!("Built chat index for {} chats", index.len()));
+        }
+        if store::get_by_label(&self.store_id, LEGACY_CHAT_INDEX_LABEL)?.is_some() {
+            store::remove_label(&self.store_id, LEGACY_CHAT_INDEX_LABEL)?;
+        }
+        Ok(())
+    }
+
+    // Make the index list exactly these chats, in this order, summarizing any it lacks.
+    // The index is only written when that changes it.
+    fn sync_chat_index(
+        &self,
+        chat_ids: &[String],
+    ) -> Result<Vec<ChatSummary>, Box<dyn std::error::Error>> {
+        let stored: Option<Vec<ChatSummary>> = self.read_json(CHAT_INDEX_LABEL)?;
+        let mut previous: HashMap<String, ChatSummary> = stored
+            .clone()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|summary| (summary.id.clone(), summary))
+            .collect();
+
+        let mut index = Vec::new();
+        for chat_id in chat_ids {
+            match previous.remove(chat_id) {
+                Some(summary) => index.push(summary),
+                None => {
+                    if let Some(chat_info) = self.get_chat_info(chat_id)? {
+                        index.push(self.summarize(&chat_info, None)?);
+                    }
+                }
+            }
+        }
+
+        if stored.as_ref() != Some(&index) {
+            self.write_json(CHAT_INDEX_LABEL, &index)?;
+        }
+        Ok(index)
+    }
+
+    // Bring a listed chat's summary in line with its info
+    fn update_chat_summary(&self, chat: &ChatInfo) -> Result<(), Box<dyn std::error::Error>> {
+        let mut index: Vec<ChatSummary> = match self.read_json(CHAT_INDEX_LABEL)? {
+            Some(index) => index,
+            None => return Ok(()),
+        };
+        // Chats are added to the index when they are listed
+        let Some(position) = index.iter().position(|summary| summary.id == chat.id) else {
+            return Ok(());
+        };
+
+        // Saving settings the list doesn't show leaves the index as it is
+        let summary = self.summarize(chat, Some(&index[position]))?;
+        if summary == index[position] {
+            return Ok(());
+        }
+        index[position] = summary;
+        self.write_json(CHAT_INDEX_LABEL, &index)
+    }
+
+    // Summarize a chat. Given its previous summary, a head that moved on by one message is
+    // counted from there instead of walking the whole conversation again. The chat was last
+    // updated when its head entry was written.
+    fn summarize(
+        &self,
+        chat: &ChatInfo,
+        previous: Option<&ChatSummary>,
+    ) -> Result<ChatSummary, Box<dyn std::error::Error>> {
+        let (message_count, last_model, updated_at) = match (previous, &chat.head) {
+            (Some(previous), head) if previous.head == *head => (
+                previous.message_count,
+                previous.last_model.clone(),
+                previous.updated_at,
+            ),
+            (_, None) => (0, None, chat.created_at),
+            (previous, Some(head)) => {
+                let entry = self.read_entry(head)?;
+                let updated_at = entry.timestamp.or(chat.created_at);
+                match previous {
+                    Some(previous)
+                        if previous.head.is_some()
+                            && entry.parents.first() == previous.head.as_ref() =>
+                    {
+                        (
+                            previous.message_count + 1,
+                            entry_model(&entry).or_else(|| previous.last_model.clone()),
+                            updated_at,
+                        )
                     }
-                    Err(e) => {
-                        log(&format!("Warning: Failed to list chat IDs: {}", e));
-                        // Continue anyway since the chat itself was created
+                    _ => {
+                        let (message_count, last_model) = self.conversation_stats(head)?;
+                        (message_count, last_model, updated_at)
                     }
                 }
+            }
+        };
+
+        Ok(ChatSummary {
+            id: chat.id.clone(),
+            name: chat.name.clone(),
+            icon: chat.icon.clone(),
+            forked_from: chat.forked_from.clone(),
+            head: chat.head.clone(),
+            created_at: chat.created_at,
+            updated_at,
+            message_count,
+            last_model,
+        })
+    }
 
-                log(&format!("Created chat with ID: {}", id));
-                Ok(chat_info)
+    // Count the entries reachable from a head and find the model of the latest reply
+    fn conversation_stats(
+        &self,
+        head: &str,
+    ) -> Result<(u64, Option<String>), Box<dyn std::error::Error>> {
+        let mut seen = std::collections::HashSet::new();
+        let mut pending = vec![head.to_string()];
+        let mut last_model = None;
+
+        // Depth first along first parents, so the first reply found is the latest one
+        while let Some(id) = pending.pop() {
+            if !seen.insert(id.clone()) {
+                continue;
             }
-            Err(e) => {
-                log(&format!("Failed to store chat info: {}", e));
-                Err(e)
+            let entry = self.read_entry(&id)?;
+            if last_model.is_none() {
+                last_model = entry_model(&entry);
             }
+            pending.extend(entry.parents.into_iter().rev());
         }
+
+        Ok((seen.len() as u64, last_model))
+    }
+
+    // Read an entry straight from the store, bypassing the cache
+    fn read_entry(&self, id: &str) -> Result<ChainEntry, Box<dyn std::error::Error>> {
+        let content = store::get(
+            &self.store_id,
+            &ContentRef {
+                hash: id.to_string(),
+            },
+        )?;
+        Ok(serde_json::from_slice(&content)?)
     }
 
     /// Update chat information
@@ -410,6 +953,9 @@ impl MessageStore {
         match label_result {
             Ok(_) => {
                 log(&format!("Updated chat info for {}", chat.id));
+                if let Err(e) = self.update_chat_summary(chat) {
+                    log(&format!("[ERROR] Failed to update chat index: {}", e));
+                }
                 Ok(())
             }
             Err(e) => {
@@ -419,28 +965,113 @@ impl MessageStore {
         }
     }
 
-    /// Delete a chat
+    /// Delete a chat by moving it to the trash, from where it can be restored until purged
     pub fn delete_chat(&mut self, chat_id: &str) -> Result<(), Box<dyn std::error::Error>> {
         log(&format!("Deleting chat {}", chat_id));
 
-        // Remove from the list of chats
-        let mut chat_ids = self.list_chat_ids()?;
-        chat_ids.retain(|id| id != chat_id);
+        let chat_info = self
+            .get_chat_info(chat_id)?
+            .ok_or_else(|| format!("Chat {} not found", chat_id))?;
 
-        // Update the chats list
-        let content = serde_json::to_vec(&chat_ids)?;
-        let content_ref = store::store(&self.store_id, &content)?;
-        store::replace_at_label(&self.store_id, "chats", &content_ref)?;
+        // Into the trash first, so a failed list update can't leave the chat unreachable
+        let mut trash = self.list_trash()?;
+        trash.retain(|trashed| trashed.id != chat_id);
+        trash.push(TrashedChat {
+            id: chat_info.id,
+            name: chat_info.name,
+            head: chat_info.head,
+            deleted_at: timing::now(),
+        });
+        self.write_json(TRASH_LABEL, &trash)?;
 
-        // We don't delete the actual chat data to allow for potential recovery
-        // But we could remove the label if desired
-        // let chat_label = format!("chat_{}", chat_id);
-        // store::remove_label(&self.store_id, &chat_label)?;
+        // Remove from the list of chats
+        self.update_chat_ids(|chat_ids| chat_ids.retain(|id| id != chat_id))?;
 
-        log(&format!("Deleted chat {}", chat_id));
+        log(&format!("Moved chat {} to the trash", chat_id));
         Ok(())
     }
 
+    /// List the chats in the trash, oldest deletion first
+    pub fn list_trash(&self) -> Result<Vec<TrashedChat>, Box<dyn std::error::Error>> {
+        Ok(self.read_json(TRASH_LABEL)?.unwrap_or_default())
+    }
+
+    /// Take a chat out of the trash and put it back in the chat list, head and all
+    pub fn restore_chat(&mut self, chat_id: &str) -> Result<ChatInfo, Box<dyn std::error::Error>> {
+        let mut trash = self.list_trash()?;
+        if !trash.iter().any(|trashed| trashed.id == chat_id) {
+            return Err(format!("Chat {} is not in the trash", chat_id).into());
+        }
+        let chat_info = self
+            .get_chat_info(chat_id)?
+            .ok_or_else(|| format!("Chat {} has no chat info left", chat_id))?;
+
+        self.update_chat_ids(|chat_ids| {
+            if !chat_ids.iter().any(|id| id == chat_id) {
+                chat_ids.push(chat_id.to_string());
+            }
+        })?;
+        trash.retain(|trashed| trashed.id != chat_id);
+        self.write_json(TRASH_LABEL, &trash)?;
+
+        log(&format!("Restored chat {} from the trash", chat_id));
+        Ok(chat_info)
+    }
+
+    /// Permanently delete the given chats from the trash by removing their labels. Messages
+    /// are left in the store, as other chats may share them. Returns the IDs purged.
+    pub fn purge_chats(
+        &mut self,
+        chat_ids: &[String],
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let mut trash = self.list_trash()?;
+        let mut purged = Vec::new();
+
+        for chat_id in chat_ids {
+            if !trash.iter().any(|trashed| &trashed.id == chat_id) {
+                continue;
+            }
+            for label in [
+                format!("chat_{}", chat_id),
+                roots_label(chat_id),
+                legacy_roots_label(chat_id),
+            ] {
+                if store::get_by_label(&self.store_id, &label)?.is_some() {
+                    store::remove_label(&self.store_id, &label)?;
+                }
+            }
+            let children_prefix = children_label(chat_id, "");
+            for label in store::list_labels(&self.store_id)? {
+                if label.starts_with(&children_prefix) {
+                    store::remove_label(&self.store_id, &label)?;
+                }
+            }
+            trash.retain(|trashed| &trashed.id != chat_id);
+            purged.push(chat_id.clone());
+        }
+
+        if !purged.is_empty() {
+            self.write_json(TRASH_LABEL, &trash)?;
+            log(&format!("Purged chats from the trash: {:?}", purged));
+        }
+        Ok(purged)
+    }
+
+    /// Purge every chat that has been in the trash longer than the retention period
+    pub fn purge_expired_trash(
+        &mut self,
+        retention_days: u32,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let cutoff = timing::now().saturating_sub(retention_days as u64 * 86_400_000);
+        let expired: Vec<String> = self
+            .list_trash()?
+            .into_iter()
+            .filter(|trashed| trashed.deleted_at <= cutoff)
+            .map(|trashed| trashed.id)
+            .collect();
+        self.purge_chats(&expired)
+    }
+
     /// Migrate legacy chat to the new format
     pub fn migrate_legacy_chat(&mut self) -> Result<Option<String>, Box<dyn std::error::Error>> {
         log("Checking for legacy chat to migrate");
@@ -470,3 +1101,48 @@ impl MessageStore {
         Ok(Some(chat_info.id))
     }
 }
+
+// The model that wrote an entry, if it is a reply
+fn entry_model(entry: &ChainEntry) -> Option<String> {
+    match &entry.data {
+        MessageData::Chat(Message::Assistant(message)) => Some(message.model.clone()),
+        _ => None,
+    }
+}
+
+// Label holding the IDs of a message's children within a chat
+fn children_label(chat_id: &str, id: &str) -> String {
+    format!("children_{}_{}", chat_id, id)
+}
+
+// Children label from before the index was kept per chat, shared by every chat
+fn legacy_children_label(id: &str) -> String {
+    format!("children_{}", id)
+}
+
+/// Split a children index label into its chat ID, `None` for a legacy label, and the
+/// ID of the message whose children it lists
+pub fn parse_children_label(label: &str) -> Option<(Option<&str>, &str)> {
+    let rest = label.strip_prefix("children_")?;
+    Some(match rest.rsplit_once('_') {
+        Some((chat_id, id)) => (Some(chat_id), id),
+        None => (None, rest),
+    })
+}
+
+// Label holding the IDs of a chat's parentless messages
+fn roots_label(chat_id: &str) -> String {
+    format!("roots_{}", chat_id)
+}
+
+// Roots label from before it moved out of the `chat_` namespace
+fn legacy_roots_label(chat_id: &str) -> String {
+    format!("chat_roots_{}", chat_id)
+}
+
+/// The chat ID of a roots index label, old name included
+pub fn parse_roots_label(label: &str) -> Option<&str> {
+    label
+        .strip_prefix("roots_")
+        .or_else(|| label.strip_prefix("chat_roots_"))
+}
@@ -0,0 +1,37 @@
+use crate::messages::Message;
+
+// Rough characters-per-token ratio for English text across common tokenizers
+const CHARS_PER_TOKEN: usize = 4;
+
+// Role markers and separators each message adds on top of its content
+const MESSAGE_OVERHEAD_TOKENS: u32 = 4;
+
+/// Approximate the number of tokens in a piece of text without a tokenizer
+pub fn estimate_tokens(text: &str) -> u32 {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN) as u32
+}
+
+/// Approximate the prompt tokens a message will take up in a request
+pub fn estimate_message_tokens(message: &Message) -> u32 {
+    let content_tokens = match message {
+        Message::System(msg) => estimate_tokens(&msg.content),
+        Message::User(msg) => estimate_tokens(&msg.content),
+        Message::Assistant(msg) => {
+            estimate_tokens(&msg.content)
+                + msg
+                    .tool_calls
+                    .iter()
+                    .flatten()
+                    .map(|call| estimate_tokens(&call.name) + estimate_tokens(&call.arguments))
+                    .sum::<u32>()
+        }
+        Message::Tool(msg) => estimate_tokens(&msg.content),
+    };
+
+    content_tokens + MESSAGE_OVERHEAD_TOKENS
+}
+
+/// Approximate the prompt tokens for a whole conversation
+pub fn estimate_prompt_tokens(messages: &[Message]) -> u32 {
+    messages.iter().map(estimate_message_tokens).sum()
+}
@@ -1,56 +1,142 @@
-use crate::api::openrouter::OpenRouterClient;
+use crate::api::error::GenerationError;
+use crate::api::retry::RetryConfig;
+use crate::api::{message_id, Providers};
+use crate::backup::{self, Archive, RestoreError, RestoreReport};
 use crate::bindings::ntwk::theater::runtime::log;
+use crate::bindings::ntwk::theater::timing;
+use crate::context::summary::{self, SummarizationConfig};
+use crate::context::{self, ContextItem, ContextStrategy, ContextWindow};
+use crate::import::ImportedConversation;
 use crate::mcp_server::{McpServer, McpServerConfig};
 use crate::messages::store::MessageStore;
+use crate::messages::tokens::{estimate_message_tokens, estimate_prompt_tokens};
 use crate::messages::{
-    AssistantMessage, ChainEntry, ChatInfo, Message, MessageData, ModelInfo, ToolMessage,
-    UserMessage,
+    AssistantMessage, ChainEntry, ChatInfo, ForkOrigin, GenerationParams, Message, MessageData,
+    SystemMessage, ToolCall, ToolMessage, UserMessage,
 };
+use crate::titles::{self, GeneratedTitle, TitlingConfig};
+use crate::usage::budget::Budgets;
+use crate::usage::{day_key, message_usage, UsageLedger};
 
 use mcp_protocol::types::tool::Tool;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::error::Error;
 
+// Upper bound on model/tool round trips for a single generation request
+const MAX_TOOL_ROUNDS: u32 = 10;
+
+// How many times a reply cut off by the token limit is continued unless configured otherwise
+pub const DEFAULT_MAX_CONTINUATION_ROUNDS: u32 = 3;
+
+// How long deleted chats stay in the trash unless configured otherwise
+pub const DEFAULT_TRASH_RETENTION_DAYS: u32 = 30;
+
+// Sent as a transient user turn when asking the model to pick up a cut-off reply
+const CONTINUATION_PROMPT: &str =
+    "Your previous reply was cut off. Continue exactly where it stopped, without repeating anything.";
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ChildActor {
     pub actor_id: String,
     pub manifest_name: String,
 }
 
+/// How generation behaves, as configured in the init data
+pub struct GenerationSettings {
+    pub max_continuation_rounds: u32,
+    pub retry_config: RetryConfig,
+    pub budgets: Budgets,
+    pub context_strategy: ContextStrategy,
+    pub summarization: Option<SummarizationConfig>,
+    pub titling: Option<TitlingConfig>,
+}
+
+/// How chats and messages are kept, as configured in the init data
+pub struct StoreSettings {
+    pub trash_retention_days: u32,
+    pub cache_capacity: usize, // Messages kept in memory while handling a request
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct State {
     pub id: String,
     pub head: Option<String>,
     pub current_chat_id: Option<String>,
-    pub openrouter_client: OpenRouterClient,
+    pub providers: Providers,
     pub connected_clients: HashMap<String, bool>,
     pub store: MessageStore,
     pub server_id: u64,
     pub mcp_servers: Vec<McpServer>,
+    pub max_continuation_rounds: u32,
+    pub retry_config: RetryConfig,
+    pub budgets: Budgets,
+    pub context_strategy: ContextStrategy,
+    pub summarization: Option<SummarizationConfig>,
+    pub titling: Option<TitlingConfig>,
+    pub trash_retention_days: u32,
 }
 
 impl State {
     pub fn new(
         id: String,
         store_id: String,
-        openrouter_api_key: String,
+        providers: Providers,
         server_id: u64,
         mcp_server_configs: Option<Vec<McpServerConfig>>,
-        model_configs: Vec<ModelInfo>,
+        settings: GenerationSettings,
+        store_settings: StoreSettings,
     ) -> Self {
+        let GenerationSettings {
+            max_continuation_rounds,
+            retry_config,
+            budgets,
+            context_strategy,
+            summarization,
+            titling,
+        } = settings;
+
+        let StoreSettings {
+            trash_retention_days,
+            cache_capacity,
+        } = store_settings;
+
+        let store = MessageStore::with_cache_capacity(store_id.clone(), cache_capacity);
+        // Budgets changed through the API outlive the ones from the init config
+        let budgets = Budgets::load(&store, budgets.clone()).unwrap_or_else(|e| {
+            log(&format!(
+                "Failed to load budgets, using configured ones: {}",
+                e
+            ));
+            budgets
+        });
+
         let mut state = Self {
             id,
             head: None,
             current_chat_id: None,
-            openrouter_client: OpenRouterClient::new(openrouter_api_key.clone(), model_configs),
+            providers,
             connected_clients: HashMap::new(),
-            store: MessageStore::new(store_id.clone()),
+            store,
             server_id,
             mcp_servers: Vec::new(),
+            max_continuation_rounds,
+            retry_config,
+            budgets,
+            context_strategy,
+            summarization,
+            titling,
+            trash_retention_days,
         };
 
+        if let Err(e) = state.store.purge_expired_trash(trash_retention_days) {
+            log(&format!(
+                "Failed to purge expired chats from the trash: {}",
+                e
+            ));
+        }
+
         // Get the list of chats
         match state.store.list_chat_ids() {
             Ok(chat_ids) if !chat_ids.is_empty() => {
@@ -87,6 +173,13 @@ impl State {
             }
         }
 
+        if let Err(e) = state.store.ensure_search_index() {
+            log(&format!("Failed to build search index: {}", e));
+        }
+        if let Err(e) = state.store.ensure_chat_index() {
+            log(&format!("Failed to build chat index: {}", e));
+        }
+
         if state.head.is_none() && state.current_chat_id.is_some() {
             if let Ok(Some(chat_info)) = state
                 .store
@@ -100,7 +193,14 @@ impl State {
             log("Starting MCP servers");
             for config in mcp_server_configs {
                 let mut mcp_server = McpServer::new(config);
-                mcp_server.start();
+                if let Err(e) = mcp_server.start() {
+                    // Keep the server around so its failure is visible in list_tools
+                    log(&format!(
+                        "[ERROR] Failed to start MCP server {}: {}",
+                        mcp_server.name(),
+                        e
+                    ));
+                }
                 state.mcp_servers.push(mcp_server);
             }
         } else {
@@ -157,6 +257,272 @@ impl State {
         Ok(chat_info)
     }
 
+    /// Start a new chat at a message from another chat, keeping the original thread intact
+    pub fn fork_chat(
+        &mut self,
+        source_chat_id: &str,
+        message_id: &str,
+        name: Option<String>,
+    ) -> Result<ChatInfo, Box<dyn std::error::Error>> {
+        let source_chat = self
+            .store
+            .get_chat_info(source_chat_id)?
+            .ok_or_else(|| format!("Chat {} not found", source_chat_id))?;
+
+        // Make sure the fork point actually exists before creating anything
+        self.store.load_message(message_id)?;
+
+        let name = name.unwrap_or_else(|| format!("{} (fork)", source_chat.name));
+        let mut chat_info = self.store.create_chat(name, Some(message_id.to_string()))?;
+
+        chat_info.forked_from = Some(ForkOrigin {
+            chat_id: source_chat_id.to_string(),
+            message_id: message_id.to_string(),
+        });
+        chat_info.icon = source_chat.icon;
+        chat_info.system_prompt = source_chat.system_prompt;
+        chat_info.generation_params = source_chat.generation_params;
+        chat_info.title_locked = true;
+        self.store.update_chat_info(&chat_info)?;
+
+        // The fork's history belongs to it as well, so it turns up in the fork's searches
+        if let Err(e) = self
+            .store
+            .index_history_for_search(&chat_info.id, chat_info.head.clone())
+        {
+            log(&format!(
+                "[ERROR] Failed to index forked chat for search: {}",
+                e
+            ));
+        }
+
+        // Switch to the fork so the next message continues from the fork point
+        self.current_chat_id = Some(chat_info.id.clone());
+        self.head = chat_info.head.clone();
+        self.notify_head_update()?;
+
+        log(&format!(
+            "Forked chat {} from chat {} at message {}",
+            chat_info.id, source_chat_id, message_id
+        ));
+        Ok(chat_info)
+    }
+
+    /// Set or clear a chat's system prompt
+    pub fn set_system_prompt(
+        &mut self,
+        chat_id: &str,
+        system_prompt: Option<String>,
+    ) -> Result<ChatInfo, Box<dyn std::error::Error>> {
+        let mut chat_info = self
+            .store
+            .get_chat_info(chat_id)?
+            .ok_or_else(|| format!("Chat {} not found", chat_id))?;
+
+        chat_info.system_prompt = system_prompt.filter(|prompt| !prompt.trim().is_empty());
+        self.store.update_chat_info(&chat_info)?;
+
+        log(&format!("Updated system prompt for chat {}", chat_id));
+        Ok(chat_info)
+    }
+
+    /// Set or clear the context strategy a chat uses instead of the configured one
+    pub fn set_context_strategy(
+        &mut self,
+        chat_id: &str,
+        strategy: Option<ContextStrategy>,
+    ) -> Result<ChatInfo, Box<dyn std::error::Error>> {
+        let mut chat_info = self
+            .store
+            .get_chat_info(chat_id)?
+            .ok_or_else(|| format!("Chat {} not found", chat_id))?;
+
+        chat_info.context_strategy = strategy;
+        self.store.update_chat_info(&chat_info)?;
+
+        log(&format!("Updated context strategy for chat {}", chat_id));
+        Ok(chat_info)
+    }
+
+    /// Turn automatic titling on or off for a chat
+    pub fn set_auto_title(
+        &mut self,
+        chat_id: &str,
+        enabled: bool,
+    ) -> Result<ChatInfo, Box<dyn std::error::Error>> {
+        let mut chat_info = self
+            .store
+            .get_chat_info(chat_id)?
+            .ok_or_else(|| format!("Chat {} not found", chat_id))?;
+
+        chat_info.title_locked = !enabled;
+        self.store.update_chat_info(&chat_info)?;
+
+        log(&format!(
+            "Automatic titling {} for chat {}",
+            if enabled { "enabled" } else { "disabled" },
+            chat_id
+        ));
+        Ok(chat_info)
+    }
+
+    /// Pin or unpin a message so the keep_pinned strategy never drops it
+    pub fn set_message_pinned(
+        &mut self,
+        chat_id: &str,
+        message_id: &str,
+        pinned: bool,
+    ) -> Result<ChatInfo, Box<dyn std::error::Error>> {
+        let mut chat_info = self
+            .store
+            .get_chat_info(chat_id)?
+            .ok_or_else(|| format!("Chat {} not found", chat_id))?;
+
+        chat_info.pinned_messages.retain(|id| id != message_id);
+        if pinned {
+            self.store.load_message(message_id)?;
+            chat_info.pinned_messages.push(message_id.to_string());
+        }
+        self.store.update_chat_info(&chat_info)?;
+
+        log(&format!(
+            "{} message {} in chat {}",
+            if pinned { "Pinned" } else { "Unpinned" },
+            message_id,
+            chat_id
+        ));
+        Ok(chat_info)
+    }
+
+    /// Store imported conversations as new chats, one chat per conversation. The current
+    /// chat stays open.
+    pub fn import_conversations(
+        &mut self,
+        conversations: Vec<ImportedConversation>,
+    ) -> Result<Vec<ChatInfo>, Box<dyn std::error::Error>> {
+        let mut chats = Vec::new();
+
+        for conversation in conversations {
+            let mut chat_info = self.store.create_chat(conversation.name, None)?;
+            let mut entry_ids: HashMap<String, String> = HashMap::new();
+            let mut entries = Vec::new();
+
+            for imported in conversation.messages {
+                let parents = imported
+                    .parent
+                    .and_then(|parent| entry_ids.get(&parent).cloned())
+                    .into_iter()
+                    .collect();
+                let entry = self.store.store_entry(
+                    ChainEntry {
+                        parents,
+                        id: None,
+                        data: MessageData::Chat(imported.message),
+                        timestamp: imported.timestamp,
+                    },
+                    &chat_info.id,
+                )?;
+                entry_ids.insert(imported.source_id, entry.id.clone().unwrap_or_default());
+                entries.push((entry, chat_info.id.clone()));
+            }
+
+            chat_info.head = conversation
+                .head
+                .and_then(|head| entry_ids.get(&head).cloned());
+            chat_info.title_locked = true;
+            self.store.update_chat_info(&chat_info)?;
+
+            if let Err(e) = self.store.index_for_search(&entries) {
+                log(&format!("Failed to index imported chat for search: {}", e));
+            }
+            log(&format!(
+                "Imported {} messages into chat {}",
+                entries.len(),
+                chat_info.id
+            ));
+            chats.push(chat_info);
+        }
+
+        self.notify_chats_update()?;
+        Ok(chats)
+    }
+
+    /// Restore a backup archive into a new store and switch over to it. The current store
+    /// is left untouched, so a failed restore changes nothing.
+    pub fn restore_backup(&mut self, archive: &Archive) -> Result<RestoreReport, RestoreError> {
+        let report = backup::restore(archive)?;
+
+        self.store =
+            MessageStore::with_cache_capacity(report.store_id.clone(), self.store.cache.capacity());
+        match Budgets::load(&self.store, self.budgets.clone()) {
+            Ok(budgets) => self.budgets = budgets,
+            Err(e) => log(&format!("Failed to load restored budgets: {}", e)),
+        }
+        if let Err(e) = self.store.ensure_search_index() {
+            log(&format!(
+                "Failed to rebuild search index after restore: {}",
+                e
+            ));
+        }
+        if let Err(e) = self.store.ensure_chat_index() {
+            log(&format!(
+                "Failed to rebuild chat index after restore: {}",
+                e
+            ));
+        }
+
+        self.current_chat_id = None;
+        self.head = None;
+        match archive.chats.first() {
+            Some(chat_id) => {
+                if let Err(e) = self.switch_chat(chat_id) {
+                    log(&format!("Failed to open restored chat {}: {}", chat_id, e));
+                }
+            }
+            None => {
+                if let Err(e) = self.create_chat("New Chat".to_string(), None) {
+                    log(&format!("Failed to create chat after restore: {}", e));
+                }
+            }
+        }
+        if let Err(e) = self.notify_chats_update() {
+            log(&format!(
+                "Failed to notify clients of restored chats: {}",
+                e
+            ));
+        }
+
+        log(&format!("Switched to restored store {}", report.store_id));
+        Ok(report)
+    }
+
+    /// Replace the spending budgets and persist them
+    pub fn set_budgets(&mut self, budgets: Budgets) -> Result<(), Box<dyn std::error::Error>> {
+        budgets.save(&self.store)?;
+        self.budgets = budgets;
+
+        log("Updated spending budgets");
+        Ok(())
+    }
+
+    /// Set or clear a chat's generation params
+    pub fn set_generation_params(
+        &mut self,
+        chat_id: &str,
+        params: Option<GenerationParams>,
+    ) -> Result<ChatInfo, Box<dyn std::error::Error>> {
+        let mut chat_info = self
+            .store
+            .get_chat_info(chat_id)?
+            .ok_or_else(|| format!("Chat {} not found", chat_id))?;
+
+        chat_info.generation_params = params;
+        self.store.update_chat_info(&chat_info)?;
+
+        log(&format!("Updated generation params for chat {}", chat_id));
+        Ok(chat_info)
+    }
+
     pub fn delete_chat(&mut self, chat_id: &str) -> Result<(), Box<dyn std::error::Error>> {
         // Delete the chat
         self.store.delete_chat(chat_id)?;
@@ -180,10 +546,41 @@ impl State {
             }
         }
 
+        if let Err(e) = self.store.purge_expired_trash(self.trash_retention_days) {
+            log(&format!(
+                "Failed to purge expired chats from the trash: {}",
+                e
+            ));
+        }
+
         log(&format!("Deleted chat: {}", chat_id));
         Ok(())
     }
 
+    /// Bring a chat back from the trash
+    pub fn restore_chat(&mut self, chat_id: &str) -> Result<ChatInfo, Box<dyn std::error::Error>> {
+        let chat_info = self.store.restore_chat(chat_id)?;
+        self.notify_chats_update()?;
+        Ok(chat_info)
+    }
+
+    /// Permanently delete one chat from the trash, or all of them when `chat_id` is None
+    pub fn purge_trash(
+        &mut self,
+        chat_id: Option<&str>,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let chat_ids: Vec<String> = match chat_id {
+            Some(chat_id) => vec![chat_id.to_string()],
+            None => self
+                .store
+                .list_trash()?
+                .into_iter()
+                .map(|trashed| trashed.id)
+                .collect(),
+        };
+        self.store.purge_chats(&chat_ids)
+    }
+
     pub fn add_to_chain(&mut self, data: MessageData, parents: Vec<String>) -> ChainEntry {
         log(&format!(
             "[DEBUG] Adding message to chain with {} parents: {:?}",
@@ -214,6 +611,7 @@ impl State {
             parents,
             id: None,
             data,
+            timestamp: Some(timing::now()),
         };
 
         // Save to runtime store
@@ -275,117 +673,960 @@ impl State {
         ));
     }
 
+    /// Generate replies until the model is done. Every `GenerationError` this returns has
+    /// already been broadcast to all clients as an `error` frame.
     pub fn generate_llm_response(
         &mut self,
         model_id: String,
+        overrides: GenerationParams,
     ) -> Result<(), Box<dyn std::error::Error>> {
+        self.summarize_if_needed();
+
+        let mut tool_rounds = 0;
+        let mut continuation_rounds = 0;
+        let mut continuation_of = None;
+
+        // Keep calling the model until it stops asking for tools or more room
+        loop {
+            log(&format!(
+                "[DEBUG] Generation round {}",
+                tool_rounds + continuation_rounds + 1
+            ));
+            let can_continue = continuation_rounds < self.max_continuation_rounds;
+            let assistant_msg = self.generate_single_response(
+                &model_id,
+                &overrides,
+                continuation_of.take(),
+                can_continue,
+            )?;
+            if let Some(error) = assistant_msg.error {
+                return Err(Box::new(error));
+            }
+
+            match assistant_msg.finish_reason.as_str() {
+                "stop" => {
+                    self.title_chat_if_needed();
+                    return Ok(());
+                }
+                "tool_calls" => {
+                    let tool_calls = assistant_msg.tool_calls.unwrap_or_default();
+                    if tool_calls.is_empty() {
+                        return Err(self.broadcast_generation_error(
+                            GenerationError::InvalidResponse {
+                                message: "llm requested tool calls but did not provide any"
+                                    .to_string(),
+                            },
+                            self.head.as_deref(),
+                        ));
+                    }
+                    self.handle_tool_calls(tool_calls);
+
+                    tool_rounds += 1;
+                    if tool_rounds >= MAX_TOOL_ROUNDS {
+                        return Err(self.broadcast_generation_error(
+                            GenerationError::Internal {
+                                message: format!(
+                                    "Exceeded maximum of {} tool call rounds",
+                                    MAX_TOOL_ROUNDS
+                                ),
+                            },
+                            self.head.as_deref(),
+                        ));
+                    }
+                }
+                "length" => {
+                    if !can_continue {
+                        // The reply stays in the chain, flagged as truncated
+                        log(&format!(
+                            "Reply still cut off after {} continuation rounds",
+                            continuation_rounds
+                        ));
+                        let _ = self.broadcast_websocket_message(
+                            &json!({
+                                "type": "response_truncated",
+                                "current_chat_id": self.current_chat_id,
+                                "message_id": self.head,
+                                "continuation_rounds": continuation_rounds,
+                            })
+                            .to_string(),
+                        );
+                        return Ok(());
+                    }
+
+                    continuation_rounds += 1;
+                    continuation_of = self.head.clone();
+                }
+                // Any other finish reason carries an error and was handled above
+                other => {
+                    return Err(self.broadcast_generation_error(
+                        GenerationError::InvalidResponse {
+                            message: format!("Unexpected finish reason: {}", other),
+                        },
+                        self.head.as_deref(),
+                    ))
+                }
+            }
+        }
+    }
+
+    // Send the current chain to the model once and append its reply to the chain
+    //
+    // `continuation_of` is the entry being continued after a "length" stop, and
+    // `can_continue` says whether another continuation may follow this reply.
+    // Failed and filtered turns are also appended, carrying their error.
+    fn generate_single_response(
+        &mut self,
+        model_id: &str,
+        overrides: &GenerationParams,
+        continuation_of: Option<String>,
+        can_continue: bool,
+    ) -> Result<AssistantMessage, Box<dyn std::error::Error>> {
         log("[DEBUG] Getting messages for LLM response");
-        let messages = self.get_anthropic_messages();
+        let context = self.build_context(model_id, overrides);
+        let _ = self.broadcast_websocket_message(
+            &json!({
+                "type": "context_info",
+                "current_chat_id": self.current_chat_id,
+                "model_id": model_id,
+                "context": context,
+            })
+            .to_string(),
+        );
+        let mut messages = context.messages;
+        if continuation_of.is_some() {
+            messages.push(Message::User(UserMessage {
+                content: CONTINUATION_PROMPT.to_string(),
+            }));
+        }
         log(&format!("[DEBUG] Got {} messages", messages.len()));
 
+        // Refuse before anything is sent or added to the chain
+        let params = self
+            .resolved_params(model_id, overrides)
+            .unwrap_or_else(|_| overrides.clone());
+        if let Err(error) = self.check_budget(model_id, &messages, &params) {
+            log(&format!("Refusing generation: {}", error));
+            return Err(self.broadcast_generation_error(error, None));
+        }
+
         // Get current head as parent
-        let mut parents = Vec::new();
-        if let Some(chat_id) = &self.current_chat_id {
-            if let Ok(Some(chat_info)) = self.store.get_chat_info(chat_id) {
-                if let Some(head) = chat_info.head {
-                    parents.push(head);
+        let parents = self.current_head_parents();
+
+        let result = self.request_with_fallbacks(model_id, overrides, messages);
+
+        let answered_by = result
+            .as_ref()
+            .ok()
+            .map(|(_, answered_by)| answered_by.clone());
+        let mut assistant_msg = match result {
+            Ok((mut assistant_msg, _)) => {
+                log(&format!("Generated completion: {:?}", assistant_msg));
+                assistant_msg.truncated = assistant_msg.finish_reason == "length" && !can_continue;
+                assistant_msg.error = finish_reason_error(&assistant_msg);
+                assistant_msg
+            }
+            Err(e) => {
+                log(&format!("Failed to generate completion: {}", e));
+                failed_message(model_id, GenerationError::from_boxed(e.as_ref()))
+            }
+        };
+        assistant_msg.continuation_of = continuation_of;
+
+        // Add LLM response to chain with all parents
+        let entry = self.add_to_chain(
+            MessageData::Chat(Message::Assistant(assistant_msg.clone())),
+            parents,
+        );
+
+        if let Some(answered_by) = answered_by {
+            self.record_usage(&answered_by, &assistant_msg);
+        }
+
+        if let Some(error) = &assistant_msg.error {
+            // Notify clients about the error
+            let _ = self.broadcast_websocket_message(
+                &self
+                    .generation_error_frame(error, entry.id.as_deref())
+                    .to_string(),
+            );
+        }
+
+        Ok(assistant_msg)
+    }
+
+    // Try the requested model, then each of its fallbacks while the failure looks like the
+    // model being unavailable. The reply names the requested model if another one answered,
+    // and comes back with the configured ID of the model that did.
+    // Fallbacks no provider serves, or that would break a budget, are skipped.
+    fn request_with_fallbacks(
+        &self,
+        model_id: &str,
+        overrides: &GenerationParams,
+        messages: Vec<Message>,
+    ) -> Result<(AssistantMessage, String), Box<dyn std::error::Error>> {
+        let mut candidates = vec![model_id.to_string()];
+        if let Ok(provider) = self.providers.for_model(model_id) {
+            candidates.extend(provider.fallback_models(model_id));
+        }
+
+        let mut last_error = None;
+        for (i, candidate) in candidates.iter().enumerate() {
+            if i > 0 {
+                if self.providers.for_model(candidate).is_err() {
+                    log(&format!(
+                        "Skipping fallback {} for {}: no configured provider serves it",
+                        candidate, model_id
+                    ));
+                    continue;
+                }
+                // The requested model was checked before anything was sent
+                let params = self.resolved_params(candidate, overrides)?;
+                if let Err(error) = self.check_budget(candidate, &messages, &params) {
+                    log(&format!("Skipping fallback {}: {}", candidate, error));
+                    last_error = Some(Box::new(error) as Box<dyn std::error::Error>);
+                    continue;
                 }
+
+                log(&format!("Falling back from {} to {}", model_id, candidate));
+                let _ = self.broadcast_websocket_message(
+                    &json!({
+                        "type": "model_fallback",
+                        "current_chat_id": self.current_chat_id,
+                        "requested_model": model_id,
+                        "model_id": candidate,
+                    })
+                    .to_string(),
+                );
+            }
+
+            match self.request_with_retries(candidate, overrides, messages.clone()) {
+                Ok(mut assistant_msg) => {
+                    if i > 0 {
+                        assistant_msg.requested_model = Some(model_id.to_string());
+                    }
+                    return Ok((assistant_msg, candidate.clone()));
+                }
+                Err(e) => {
+                    let error = GenerationError::from_boxed(e.as_ref());
+                    let unavailable = error.retryable()
+                        || matches!(error, GenerationError::Provider { status: 404, .. });
+                    if !unavailable {
+                        return Err(e);
+                    }
+                    log(&format!("Model {} unavailable: {}", candidate, error));
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| "No model to send the request to".into()))
+    }
+
+    // Make a request, retrying rate limits, server errors and network failures with backoff
+    fn request_with_retries(
+        &self,
+        model_id: &str,
+        overrides: &GenerationParams,
+        messages: Vec<Message>,
+    ) -> Result<AssistantMessage, Box<dyn std::error::Error>> {
+        let mut attempt = 0;
+        loop {
+            let e = match self.request_completion(model_id, overrides, messages.clone()) {
+                Ok(assistant_msg) => return Ok(assistant_msg),
+                Err(e) => e,
+            };
+
+            let error = GenerationError::from_boxed(e.as_ref());
+            let delay = match self.retry_config.delay_for(attempt, &error) {
+                Some(delay) => delay,
+                None => return Err(e),
+            };
+            attempt += 1;
+
+            log(&format!(
+                "Request to {} failed ({}), retry {} of {} in {}ms",
+                model_id, error, attempt, self.retry_config.max_retries, delay
+            ));
+            // Lets clients show that the reply is delayed rather than stuck
+            let _ = self.broadcast_websocket_message(
+                &json!({
+                    "type": "generation_retry",
+                    "current_chat_id": self.current_chat_id,
+                    "model_id": model_id,
+                    "attempt": attempt,
+                    "delay_ms": delay,
+                    "code": error.code(),
+                })
+                .to_string(),
+            );
+
+            if let Err(sleep_error) = timing::sleep(delay) {
+                log(&format!("Backoff sleep failed: {}", sleep_error));
+                return Err(e);
             }
         }
+    }
 
+    // The model's default params, overridden by the current chat's, then by `overrides`
+    fn resolved_params(
+        &self,
+        model_id: &str,
+        overrides: &GenerationParams,
+    ) -> Result<GenerationParams, Box<dyn std::error::Error>> {
+        let chat_params = match self.get_current_chat() {
+            Ok(Some(chat_info)) => chat_info.generation_params.unwrap_or_default(),
+            _ => GenerationParams::default(),
+        };
+        let provider = self.providers.for_model(model_id)?;
+        Ok(provider
+            .default_params(model_id)
+            .merged_with(&chat_params)
+            .merged_with(overrides))
+    }
+
+    // Resolve the provider and generation params for a model and make a single request
+    fn request_completion(
+        &self,
+        model_id: &str,
+        overrides: &GenerationParams,
+        messages: Vec<Message>,
+    ) -> Result<AssistantMessage, Box<dyn std::error::Error>> {
         // Determine which provider to use based on model ID
-        let tools = self.get_tools();
+        let provider = self.providers.for_model(model_id)?;
+        let params = self.resolved_params(model_id, overrides)?;
+        let tools = if provider.supports_tools(model_id) {
+            self.get_tools()
+        } else {
+            None
+        };
 
         // Call appropriate client
-        let result = self
-            .openrouter_client
-            .generate_response(messages, model_id, tools);
+        provider.generate_response(messages, model_id.to_string(), tools, &params)
+    }
 
-        match result {
-            Ok(assistant_msg) => {
-                log(&format!("Generated completion: {:?}", assistant_msg));
+    /// Summarize the older part of the current chat with the summarization model. The summary
+    /// is added to the chain and sent in place of the entries it covers from then on.
+    /// Returns `None` when there is nothing new to summarize.
+    pub fn summarize_chat(&mut self) -> Result<Option<ChainEntry>, Box<dyn std::error::Error>> {
+        match self.current_chat_id.clone() {
+            Some(chat_id) => self.summarize_chat_by_id(&chat_id),
+            None => Ok(None),
+        }
+    }
+
+    /// Summarize any chat the same way as `summarize_chat`, without switching to it
+    pub fn summarize_chat_by_id(
+        &mut self,
+        chat_id: &str,
+    ) -> Result<Option<ChainEntry>, Box<dyn std::error::Error>> {
+        let config = self
+            .summarization
+            .clone()
+            .ok_or("No summarization model configured")?;
 
-                // Add LLM response to chain with all parents
-                self.add_to_chain(
-                    MessageData::Chat(Message::Assistant(assistant_msg.clone())),
+        let chat_info = self
+            .store
+            .get_chat_info(chat_id)?
+            .ok_or_else(|| format!("Chat {} not found", chat_id))?;
+        let chain = self.chain_from(chat_info.head.clone());
+        let items = Self::chain_items(chain, &[]);
+        let end = match summary::span_end(&items, config.keep_recent) {
+            Some(end) => end,
+            None => return Ok(None),
+        };
+        let span = &items[..end];
+        let covers: Vec<String> = span
+            .iter()
+            .flat_map(|item| item.entry_ids.iter().cloned())
+            .collect();
+
+        let messages = summary::request_messages(span);
+        let provider = self.providers.for_model(&config.model_id)?;
+        let params = provider.default_params(&config.model_id);
+        self.check_budget(&config.model_id, &messages, &params)?;
+
+        let reply = provider.generate_response(messages, config.model_id.clone(), None, &params)?;
+        self.record_chat_usage(chat_id, &config.model_id, &reply);
+        if let Some(error) = finish_reason_error(&reply) {
+            return Err(Box::new(error));
+        }
+        if reply.content.trim().is_empty() {
+            return Err("Summarization model returned an empty summary".into());
+        }
+
+        let data = MessageData::Summary {
+            covers: covers.clone(),
+            text: reply.content,
+            model: config.model_id,
+        };
+        let parents: Vec<String> = chat_info.head.into_iter().collect();
+        let entry = if self.current_chat_id.as_deref() == Some(chat_id) {
+            self.add_to_chain(data, parents)
+        } else {
+            // Saving moves that chat's head; the current chat and its head stay as they are
+            self.store.save_message(
+                ChainEntry {
                     parents,
-                );
+                    id: None,
+                    data,
+                    timestamp: Some(timing::now()),
+                },
+                chat_id,
+            )?
+        };
+
+        log(&format!(
+            "Summarized {} entries into {:?}",
+            covers.len(),
+            entry.id
+        ));
+        let _ = self.broadcast_websocket_message(
+            &json!({
+                "type": "chat_summarized",
+                "chat_id": chat_id,
+                "current_chat_id": self.current_chat_id,
+                "message_id": entry.id,
+                "covers": covers,
+            })
+            .to_string(),
+        );
+
+        Ok(Some(entry))
+    }
+
+    // Name the current chat after its first exchange with the titling model. Once a title
+    // has been generated the chat is locked, so this only ever happens once per chat.
+    fn title_chat_if_needed(&mut self) {
+        let config = match &self.titling {
+            Some(config) => config.clone(),
+            None => return,
+        };
+        let mut chat_info = match self.get_current_chat() {
+            Ok(Some(chat_info)) if !chat_info.title_locked => chat_info,
+            _ => return,
+        };
+
+        let chain = self.get_chain();
+        let user = chain.iter().find_map(|entry| match &entry.data {
+            MessageData::Chat(Message::User(msg)) => Some(msg.content.clone()),
+            _ => None,
+        });
+        let assistant = chain.iter().find_map(|entry| match &entry.data {
+            MessageData::Chat(Message::Assistant(msg))
+                if msg.error.is_none() && !msg.content.is_empty() =>
+            {
+                Some(msg.content.clone())
+            }
+            _ => None,
+        });
+        let (user, assistant) = match (user, assistant) {
+            (Some(user), Some(assistant)) => (user, assistant),
+            _ => return,
+        };
+
+        let GeneratedTitle { title, icon } = match self.generate_title(&config, &user, &assistant) {
+            Ok(Some(title)) => title,
+            Ok(None) => {
+                log("Titling model did not return a usable title");
+                return;
+            }
+            Err(e) => {
+                log(&format!("Failed to generate a chat title: {}", e));
+                return;
+            }
+        };
+
+        log(&format!("Titled chat {} as {:?}", chat_info.id, title));
+        chat_info.name = title;
+        if icon.is_some() {
+            chat_info.icon = icon;
+        }
+        chat_info.title_locked = true;
+        if let Err(e) = self.store.update_chat_info(&chat_info) {
+            log(&format!("Failed to save chat title: {}", e));
+            return;
+        }
+        if let Err(e) = self.notify_chats_update() {
+            log(&format!("Failed to notify chats update: {}", e));
+        }
+    }
+
+    fn generate_title(
+        &self,
+        config: &TitlingConfig,
+        user: &str,
+        assistant: &str,
+    ) -> Result<Option<GeneratedTitle>, Box<dyn std::error::Error>> {
+        let messages = titles::request_messages(config, user, assistant);
+        let provider = self.providers.for_model(&config.model_id)?;
+        let params = provider.default_params(&config.model_id);
+        self.check_budget(&config.model_id, &messages, &params)?;
+
+        let reply = provider.generate_response(messages, config.model_id.clone(), None, &params)?;
+        self.record_usage(&config.model_id, &reply);
+
+        Ok(titles::parse_reply(config, &reply.content))
+    }
+
+    // Summarize the current chat once it has grown past the configured threshold
+    fn summarize_if_needed(&mut self) {
+        let threshold = match self
+            .summarization
+            .as_ref()
+            .and_then(|config| config.threshold_tokens)
+        {
+            Some(threshold) => threshold,
+            None => return,
+        };
+
+        let tokens: u32 = self
+            .context_items(&[])
+            .iter()
+            .map(|item| estimate_message_tokens(&item.message))
+            .sum();
+        if tokens <= threshold {
+            return;
+        }
+
+        log(&format!(
+            "Chat is about {} tokens, over the {} token summarization threshold",
+            tokens, threshold
+        ));
+        if let Err(e) = self.summarize_chat() {
+            log(&format!("Automatic summarization failed: {}", e));
+        }
+    }
+
+    // Refuse a request whose worst case, the prompt plus a reply of the full `max_tokens`,
+    // would go over a budget
+    fn check_budget(
+        &self,
+        model_id: &str,
+        messages: &[Message],
+        params: &GenerationParams,
+    ) -> Result<(), GenerationError> {
+        let provider = self.providers.for_model(model_id).ok();
+        let pricing = provider.and_then(|provider| provider.pricing(model_id));
+        let input_cost_per_million_tokens = pricing
+            .as_ref()
+            .and_then(|pricing| pricing.input_cost_per_million_tokens)
+            .unwrap_or(0.0);
+        let output_cost_per_million_tokens = pricing
+            .as_ref()
+            .and_then(|pricing| pricing.output_cost_per_million_tokens)
+            .unwrap_or(0.0);
+        let max_tokens = params
+            .max_tokens
+            .or_else(|| {
+                provider
+                    .and_then(|provider| provider.model_info(model_id))
+                    .map(|model| model.max_tokens)
+            })
+            .unwrap_or(0);
+        let estimated_cost = (estimate_prompt_tokens(messages) as f64
+            * input_cost_per_million_tokens
+            + max_tokens as f64 * output_cost_per_million_tokens)
+            / 1_000_000.0;
+
+        let ledger = UsageLedger::load(&self.store).map_err(|e| GenerationError::Internal {
+            message: format!("Failed to load usage ledger: {}", e),
+        })?;
+
+        self.budgets.check(
+            &ledger,
+            self.current_chat_id.as_deref(),
+            model_id,
+            &day_key(timing::now()),
+            estimated_cost,
+        )
+    }
+
+    // Add a reply's tokens and cost to the usage ledger, under the configured ID of the model
+    // that wrote it, and push the new totals to clients
+    fn record_usage(&self, model_id: &str, assistant_msg: &AssistantMessage) {
+        if let Some(chat_id) = &self.current_chat_id {
+            self.record_chat_usage(chat_id, model_id, assistant_msg);
+        }
+    }
+
+    fn record_chat_usage(&self, chat_id: &str, model_id: &str, assistant_msg: &AssistantMessage) {
+        let mut ledger = match UsageLedger::load(&self.store) {
+            Ok(ledger) => ledger,
+            Err(e) => {
+                log(&format!("Failed to load usage ledger: {}", e));
+                return;
+            }
+        };
+        ledger.record(chat_id, timing::now(), model_id, assistant_msg);
+        if let Err(e) = ledger.save(&self.store) {
+            log(&format!("Failed to save usage ledger: {}", e));
+        }
+
+        let _ = self.broadcast_websocket_message(
+            &json!({
+                "type": "usage_update",
+                "chat_id": chat_id,
+                "model": assistant_msg.model,
+                "model_id": model_id,
+                "message": message_usage(assistant_msg),
+                "chat": ledger.chat(chat_id),
+                "total": ledger.total,
+            })
+            .to_string(),
+        );
+    }
+
+    // Tell every client that a generation failed, at `message_id` if an entry was added,
+    // and hand the error back
+    fn broadcast_generation_error(
+        &self,
+        error: GenerationError,
+        message_id: Option<&str>,
+    ) -> Box<dyn std::error::Error> {
+        let _ = self.broadcast_websocket_message(
+            &self.generation_error_frame(&error, message_id).to_string(),
+        );
+        Box::new(error)
+    }
+
+    /// The structured `error` frame sent to clients when a generation fails
+    pub fn generation_error_frame(
+        &self,
+        error: &GenerationError,
+        message_id: Option<&str>,
+    ) -> Value {
+        json!({
+            "type": "error",
+            "code": error.code(),
+            "retryable": error.retryable(),
+            "message": format!("Failed to generate AI response: {}", error),
+            "provider_message": error.message(),
+            "error": error,
+            "current_chat_id": self.current_chat_id,
+            "message_id": message_id,
+        })
+    }
+
+    // Run each requested tool and append its result to the chain as a tool message
+    fn handle_tool_calls(&mut self, tool_calls: Vec<ToolCall>) {
+        for tool_call in tool_calls {
+            log(&format!(
+                "[DEBUG] Dispatching tool call {} ({})",
+                tool_call.id, tool_call.name
+            ));
+
+            let content = match self.call_tool(&tool_call) {
+                Ok(content) => content,
+                Err(e) => {
+                    // Report the failure back to the model instead of aborting the turn
+                    log(&format!(
+                        "[ERROR] Tool call {} failed: {}",
+                        tool_call.name, e
+                    ));
+                    format!("Error: {}", e)
+                }
+            };
+
+            let parents = self.current_head_parents();
+            self.add_to_chain(
+                MessageData::Chat(Message::Tool(ToolMessage {
+                    tool_call_id: tool_call.id.clone(),
+                    content,
+                })),
+                parents,
+            );
+        }
+    }
+
+    fn call_tool(&mut self, tool_call: &ToolCall) -> Result<String, String> {
+        let arguments: Value = if tool_call.arguments.trim().is_empty() {
+            json!({})
+        } else {
+            serde_json::from_str(&tool_call.arguments)
+                .map_err(|e| format!("Invalid arguments for {}: {}", tool_call.name, e))?
+        };
+
+        let server = self
+            .mcp_servers
+            .iter_mut()
+            .find(|server| server.has_tool(&tool_call.name))
+            .ok_or_else(|| format!("Tool {} is not available", tool_call.name))?;
 
-                match assistant_msg.finish_reason.as_str() {
-                    "stop" => Ok(()),
-                    "tool_calls" => todo!(),
-                    "length" => todo!(),
-                    "content_filter" => todo!(),
-                    "error" => Err("llm request returned 200 with an error in the body".into()),
-                    _ => Err("unknown stop reason".into()),
+        server.call_tool(&tool_call.name, arguments)
+    }
+
+    fn current_head_parents(&self) -> Vec<String> {
+        let mut parents = Vec::new();
+        if let Some(chat_id) = &self.current_chat_id {
+            if let Ok(Some(chat_info)) = self.store.get_chat_info(chat_id) {
+                if let Some(head) = chat_info.head {
+                    parents.push(head);
                 }
             }
+        }
+        parents
+    }
+
+    /// Edit an earlier user message by saving the new text as a sibling and moving the head there
+    pub fn edit_message(
+        &mut self,
+        message_id: &str,
+        content: &str,
+    ) -> Result<ChainEntry, Box<dyn std::error::Error>> {
+        let original = self.store.load_message(message_id)?;
+        match original.data {
+            MessageData::Chat(Message::User(_)) => {}
+            _ => return Err(format!("Message {} is not a user message", message_id).into()),
+        }
+
+        log(&format!("Editing message {} as a new branch", message_id));
+        let entry = self.add_to_chain(
+            MessageData::Chat(Message::User(UserMessage {
+                content: content.to_string(),
+            })),
+            original.parents,
+        );
+        Ok(entry)
+    }
+
+    /// Generate a new reply as a sibling of an assistant message, or as another reply to a
+    /// user message. The previous head is restored if generation fails.
+    pub fn regenerate_response(
+        &mut self,
+        message_id: &str,
+        model_id: String,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let chat_id = self
+            .current_chat_id
+            .clone()
+            .ok_or("No current chat to regenerate in")?;
+        let entry = self.store.load_message(message_id)?;
+
+        let branch_point = match &entry.data {
+            MessageData::Chat(Message::Assistant(_)) => entry.parents.first().cloned(),
+            MessageData::Chat(Message::User(_)) => Some(message_id.to_string()),
+            _ => return Err(format!("Cannot regenerate from message {}", message_id).into()),
+        };
+
+        let previous_head = self.current_head_parents().into_iter().next();
+        self.set_head(&chat_id, branch_point)?;
+
+        if let Err(e) = self.generate_llm_response(model_id, GenerationParams::default()) {
+            log(&format!(
+                "Regeneration failed, restoring head {:?}",
+                previous_head
+            ));
+            self.set_head(&chat_id, previous_head)?;
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    /// Describe the alternative branches at a message's position in the current chat
+    pub fn list_branches(&mut self, message_id: &str) -> Result<Value, Box<dyn std::error::Error>> {
+        let chat_id = self.current_chat_id.clone().ok_or("No current chat")?;
+        let sibling_ids = self.store.list_siblings(message_id, &chat_id)?;
+
+        let mut siblings = Vec::new();
+        for id in &sibling_ids {
+            let entry = self.store.load_message(id)?;
+            let (role, content) = match &entry.data {
+                MessageData::Chat(Message::System(msg)) => ("system", msg.content.clone()),
+                MessageData::Chat(Message::User(msg)) => ("user", msg.content.clone()),
+                MessageData::Chat(Message::Assistant(msg)) => ("assistant", msg.content.clone()),
+                MessageData::Chat(Message::Tool(msg)) => ("tool", msg.content.clone()),
+                MessageData::Summary { text, .. } => ("summary", text.clone()),
+            };
+            siblings.push(json!({
+                "id": id,
+                "role": role,
+                "preview": content.chars().take(100).collect::<String>(),
+                "children": self.store.list_children(&chat_id, id)?.len(),
+            }));
+        }
+
+        Ok(json!({
+            "message_id": message_id,
+            "index": sibling_ids.iter().position(|id| id == message_id),
+            "siblings": siblings,
+        }))
+    }
+
+    /// Move the current chat's head to the newest tip of the branch starting at a message
+    pub fn switch_branch(&mut self, message_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let chat_id = self.current_chat_id.clone().ok_or("No current chat")?;
+
+        // Make sure the message exists before following its descendants
+        self.store.load_message(message_id)?;
+
+        let mut tip = message_id.to_string();
+        while let Some(child) = self.store.list_children(&chat_id, &tip)?.pop() {
+            tip = child;
+        }
+
+        log(&format!(
+            "Switching to branch at {} (tip {})",
+            message_id, tip
+        ));
+        self.set_head(&chat_id, Some(tip))
+    }
+
+    fn set_head(
+        &mut self,
+        chat_id: &str,
+        head: Option<String>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.store.set_chat_head(chat_id, head.clone())?;
+        if self.current_chat_id.as_deref() == Some(chat_id) {
+            self.head = head;
+            self.notify_head_update()?;
+        }
+        Ok(())
+    }
+
+    /// The conversation as it will be sent to `model_id`, fitted to the model's context window
+    /// with the chat's context strategy (or the configured one)
+    pub fn build_context(&mut self, model_id: &str, overrides: &GenerationParams) -> ContextWindow {
+        let chat_info = match self.get_current_chat() {
+            Ok(chat_info) => chat_info,
             Err(e) => {
-                log(&format!("Failed to generate completion: {}", e));
-                // Notify clients about the error
-                let error_message = format!("Failed to generate AI response: {}", e);
-                let _ = self.broadcast_websocket_message(
-                    &serde_json::to_string(&serde_json::json!({
-                        "type": "error",
-                        "message": error_message
-                    }))
-                    .unwrap(),
-                );
-                Err(e.into())
+                log(&format!("Failed to load chat info: {}", e));
+                None
             }
+        };
+
+        // The chat's system prompt always goes first
+        let system = chat_info
+            .as_ref()
+            .and_then(|chat_info| chat_info.system_prompt.clone())
+            .filter(|content| !content.trim().is_empty())
+            .map(|content| Message::System(SystemMessage { content }));
+        let strategy = chat_info
+            .as_ref()
+            .and_then(|chat_info| chat_info.context_strategy)
+            .unwrap_or(self.context_strategy);
+        let pinned = chat_info
+            .as_ref()
+            .map(|chat_info| chat_info.pinned_messages.clone())
+            .unwrap_or_default();
+
+        let token_limit = self
+            .providers
+            .for_model(model_id)
+            .ok()
+            .and_then(|provider| {
+                let chat_params = chat_info
+                    .as_ref()
+                    .and_then(|chat_info| chat_info.generation_params.clone())
+                    .unwrap_or_default();
+                let params = provider
+                    .default_params(model_id)
+                    .merged_with(&chat_params)
+                    .merged_with(overrides);
+                provider
+                    .model_info(model_id)
+                    .map(|model| context::prompt_token_limit(model, &params))
+            });
+
+        let items = self.context_items(&pinned);
+        let context = context::build(system, items, token_limit, strategy);
+        if !context.omitted.is_empty() {
+            log(&format!(
+                "Context for {} left out {} entries ({:?} strategy)",
+                model_id,
+                context.omitted.len(),
+                strategy
+            ));
         }
+        context
     }
 
-    pub fn get_anthropic_messages(&mut self) -> Vec<Message> {
-        let mut messages: Vec<Message> = vec![];
+    // The current chain as messages, each tagged with the entries it came from
+    fn context_items(&mut self, pinned: &[String]) -> Vec<ContextItem> {
         let chain = self.get_chain();
         log(&format!("Chain: {:?}", chain));
+        Self::chain_items(chain, pinned)
+    }
+
+    fn chain_items(chain: Vec<ChainEntry>, pinned: &[String]) -> Vec<ContextItem> {
+        let mut items: Vec<ContextItem> = Vec::new();
 
         // Process chain entries (already in chronological order)
         for entry in chain {
             log(&format!("Processing entry: {:?}", entry));
+            let entry_id = entry.id.clone().unwrap_or_default();
+            let is_pinned = pinned.contains(&entry_id);
             match entry.data {
+                // Failed and filtered turns are shown to the user but never sent back
+                MessageData::Chat(Message::Assistant(AssistantMessage {
+                    error: Some(_), ..
+                })) => continue,
                 MessageData::Chat(msg) => {
                     log(&format!("Adding message: {:?}", msg));
 
                     // If the last message is from the user, and the current message is also from
                     // the user, combine them into a single message
-                    if let Some(last_msg) = messages.last() {
-                        match (last_msg, &msg) {
+                    if let Some(last) = items.last_mut().filter(|last| !last.summary) {
+                        let merged = match (&mut last.message, &msg) {
                             (
                                 Message::User(UserMessage {
-                                    content: _last_content,
+                                    content: combined_content,
                                 }),
                                 Message::User(UserMessage { content }),
                             ) => {
-                                if let Some(Message::User(UserMessage {
-                                    content: combined_content,
-                                })) = messages.last_mut()
-                                {
-                                    combined_content.push_str(&format!("\n{}", content));
-                                    log(&format!("Updated chat message: {:?}", combined_content));
-                                    continue;
-                                }
+                                combined_content.push_str(&format!("\n{}", content));
+                                log(&format!("Updated chat message: {:?}", combined_content));
+                                true
+                            }
+                            // Pieces of a continued reply are sent back as one message
+                            (Message::Assistant(previous), Message::Assistant(next))
+                                if next.continuation_of.is_some() =>
+                            {
+                                previous.content.push_str(&next.content);
+                                previous.tool_calls = next.tool_calls.clone();
+                                true
                             }
-                            _ => {}
+                            _ => false,
+                        };
+                        if merged {
+                            last.entry_ids.push(entry_id);
+                            last.pinned |= is_pinned;
+                            continue;
                         }
                     }
 
-                    messages.push(msg.clone());
+                    items.push(ContextItem {
+                        entry_ids: vec![entry_id],
+                        message: msg,
+                        pinned: is_pinned,
+                        summary: false,
+                    });
+                }
+                // A summary replaces the entries it covers, at the position of the first one
+                MessageData::Summary { covers, text, .. } => {
+                    let is_covered =
+                        |item: &ContextItem| item.entry_ids.iter().any(|id| covers.contains(id));
+                    let position = items.iter().position(is_covered).unwrap_or(items.len());
+                    items.retain(|item| !is_covered(item));
+
+                    items.insert(
+                        position,
+                        ContextItem {
+                            entry_ids: vec![entry_id],
+                            message: summary::summary_message(&text),
+                            pinned: is_pinned,
+                            summary: true,
+                        },
+                    );
                 }
             }
         }
 
-        messages
+        items
     }
 
     pub fn get_chain(&mut self) -> Vec<ChainEntry> {
-        // Create a set to track processed message IDs
-        let mut processed_ids = std::collections::HashSet::new();
-
-        // This will store the messages in reverse order (newest first)
-        let mut reverse_chain = Vec::new();
-
         // Start with the current head
         let current_id = if let Some(chat_id) = &self.current_chat_id {
             if let Ok(Some(chat_info)) = self.store.get_chat_info(chat_id) {
@@ -397,14 +1638,37 @@ impl State {
             self.head.clone()
         };
 
+        self.chain_from(current_id)
+    }
+
+    /// The chain of any chat, oldest first, walked the same way as `get_chain`
+    pub fn get_chat_chain(
+        &mut self,
+        chat_id: &str,
+    ) -> Result<Vec<ChainEntry>, Box<dyn std::error::Error>> {
+        let chat_info = self
+            .store
+            .get_chat_info(chat_id)?
+            .ok_or_else(|| format!("Chat {} not found", chat_id))?;
+        Ok(self.chain_from(chat_info.head))
+    }
+
+    // Every entry reachable from a head, in chronological order
+    fn chain_from(&mut self, head: Option<String>) -> Vec<ChainEntry> {
+        // Create a set to track processed message IDs
+        let mut processed_ids = std::collections::HashSet::new();
+
+        // This will store the messages in reverse order (newest first)
+        let mut reverse_chain = Vec::new();
+
         // Process messages starting from the head
-        if let Some(head_id) = current_id {
+        if let Some(head_id) = head {
             self.process_message_chain(&head_id, &mut reverse_chain, &mut processed_ids);
         }
 
         // Reverse to get chronological order (oldest first)
         reverse_chain.reverse();
-        return reverse_chain;
+        reverse_chain
     }
 
     // Helper method to recursively process the DAG message chain
@@ -536,19 +1800,7 @@ impl State {
     }
 
     pub fn notify_chats_update(&self) -> Result<(), String> {
-        if let Ok(chat_ids) = self.store.list_chat_ids() {
-            let mut chats = Vec::new();
-
-            for chat_id in chat_ids {
-                if let Ok(Some(chat_info)) = self.store.get_chat_info(&chat_id) {
-                    chats.push(json!({
-                        "id": chat_info.id,
-                        "name": chat_info.name,
-                        "icon": chat_info.icon,
-                    }));
-                }
-            }
-
+        if let Ok(chats) = self.store.list_chat_summaries() {
             let message = serde_json::to_string(&serde_json::json!({
                 "type": "chats_update",
                 "chats": chats,
@@ -562,9 +1814,59 @@ impl State {
         }
     }
 
-    fn get_tools(&self) -> Option<Vec<Tool>> {
-        // Placeholder for tool retrieval logic
-        // This should return a Vec<Tool> based on the current state
-        None
+    pub fn get_tools(&self) -> Option<Vec<Tool>> {
+        let tools: Vec<Tool> = self
+            .mcp_servers
+            .iter()
+            .flat_map(|server| server.tools.iter().cloned())
+            .collect();
+
+        if tools.is_empty() {
+            None
+        } else {
+            Some(tools)
+        }
+    }
+}
+
+// The error a completed request carries in its finish reason, if any
+fn finish_reason_error(assistant_msg: &AssistantMessage) -> Option<GenerationError> {
+    match assistant_msg.finish_reason.as_str() {
+        "stop" | "tool_calls" | "length" => None,
+        "content_filter" => Some(GenerationError::ContentFiltered {
+            message: format!(
+                "{} stopped the response ({})",
+                assistant_msg.model,
+                assistant_msg
+                    .native_finish_reason
+                    .as_deref()
+                    .unwrap_or("content_filter")
+            ),
+        }),
+        "error" => Some(GenerationError::InvalidResponse {
+            message: "llm request returned 200 with an error in the body".to_string(),
+        }),
+        other => Some(GenerationError::InvalidResponse {
+            message: format!("Unknown finish reason: {}", other),
+        }),
+    }
+}
+
+// A placeholder assistant turn recording a request that produced no reply
+fn failed_message(model_id: &str, error: GenerationError) -> AssistantMessage {
+    AssistantMessage {
+        content: String::new(),
+        id: message_id(&error.to_string()),
+        model: model_id.to_string(),
+        finish_reason: "error".to_string(),
+        native_finish_reason: None,
+        usage: Default::default(),
+        input_cost_per_million_tokens: None,
+        output_cost_per_million_tokens: None,
+        tool_calls: None,
+        continuation_of: None,
+        truncated: false,
+        error: Some(error),
+        requested_model: None,
     }
 }
@@ -0,0 +1,91 @@
+use crate::messages::{Message, SystemMessage, UserMessage};
+use serde::{Deserialize, Serialize};
+
+// Titles longer than this are cut off
+const MAX_TITLE_CHARS: usize = 60;
+
+// How much of each side of the exchange is shown to the titling model
+const EXCHANGE_SNIPPET_CHARS: usize = 2000;
+
+const TITLE_INSTRUCTIONS: &str = "You name conversations. Given the opening exchange of a \
+conversation, reply with a short title of at most six words that says what it is about. \
+Reply with the title only, without quotes or a trailing period.";
+
+const TITLE_WITH_ICON_INSTRUCTIONS: &str = "You name conversations. Given the opening exchange of \
+a conversation, reply with a single emoji that fits the topic, a space, and then a short title of \
+at most six words that says what it is about. Reply with nothing else, without quotes or a \
+trailing period.";
+
+/// Naming new chats after their first exchange
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TitlingConfig {
+    pub model_id: String,
+    #[serde(default)]
+    pub pick_icon: bool, // Also ask for an emoji to use as the chat's icon
+}
+
+/// A title read from the titling model's reply
+#[derive(Debug, Clone)]
+pub struct GeneratedTitle {
+    pub title: String,
+    pub icon: Option<String>,
+}
+
+/// The request sent to the titling model
+pub fn request_messages(config: &TitlingConfig, user: &str, assistant: &str) -> Vec<Message> {
+    let instructions = if config.pick_icon {
+        TITLE_WITH_ICON_INSTRUCTIONS
+    } else {
+        TITLE_INSTRUCTIONS
+    };
+    let snippet = |text: &str| {
+        text.chars()
+            .take(EXCHANGE_SNIPPET_CHARS)
+            .collect::<String>()
+    };
+
+    vec![
+        Message::System(SystemMessage {
+            content: instructions.to_string(),
+        }),
+        Message::User(UserMessage {
+            content: format!(
+                "User: {}\n\nAssistant: {}",
+                snippet(user),
+                snippet(assistant)
+            ),
+        }),
+    ]
+}
+
+/// Pull the title, and the icon if one was asked for, out of the model's reply
+pub fn parse_reply(config: &TitlingConfig, reply: &str) -> Option<GeneratedTitle> {
+    let line = reply.lines().map(str::trim).find(|line| !line.is_empty())?;
+
+    let (icon, title) = match line.split_once(' ') {
+        // Anything but a word in front of the title is taken as the icon
+        Some((first, rest)) if config.pick_icon && !first.chars().any(|c| c.is_alphanumeric()) => {
+            (Some(first.to_string()), rest)
+        }
+        _ => (None, line),
+    };
+
+    let title = title
+        .trim()
+        .trim_matches(|c| c == '"' || c == '\'' || c == '*')
+        .trim_end_matches('.')
+        .trim();
+    if title.is_empty() {
+        return None;
+    }
+
+    let title = if title.chars().count() > MAX_TITLE_CHARS {
+        format!(
+            "{}…",
+            title.chars().take(MAX_TITLE_CHARS).collect::<String>()
+        )
+    } else {
+        title.to_string()
+    };
+    Some(GeneratedTitle { title, icon })
+}
@@ -0,0 +1,95 @@
+use crate::api::error::GenerationError;
+use crate::messages::store::MessageStore;
+use crate::usage::UsageLedger;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const BUDGETS_LABEL: &str = "budgets";
+
+/// Spending limits in USD. A generation is refused when what has already been spent plus
+/// the estimated cost of the request, prompt and longest possible reply, would go over any
+/// limit that applies to it.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Budgets {
+    pub per_chat: Option<f64>, // Limit for every chat without its own entry
+    pub chats: BTreeMap<String, f64>, // Limits for specific chats
+    pub per_model: BTreeMap<String, f64>, // Lifetime limits for specific models
+    pub daily: Option<f64>,    // Across all chats, per UTC day
+    pub monthly: Option<f64>,  // Across all chats, per UTC month
+}
+
+impl Budgets {
+    /// The budgets saved through the API, or `defaults` (from the init config) if none were
+    pub fn load(
+        store: &MessageStore,
+        defaults: Budgets,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(store.read_json(BUDGETS_LABEL)?.unwrap_or(defaults))
+    }
+
+    pub fn save(&self, store: &MessageStore) -> Result<(), Box<dyn std::error::Error>> {
+        store.write_json(BUDGETS_LABEL, self)
+    }
+
+    /// Check every budget that applies to a request before it is sent
+    pub fn check(
+        &self,
+        ledger: &UsageLedger,
+        chat_id: Option<&str>,
+        model_id: &str,
+        day: &str,
+        estimated_cost: f64,
+    ) -> Result<(), GenerationError> {
+        let mut limits = Vec::new();
+
+        if let Some(chat_id) = chat_id {
+            if let Some(limit) = self.chats.get(chat_id).copied().or(self.per_chat) {
+                limits.push((
+                    format!("chat {}", chat_id),
+                    limit,
+                    ledger.chat(chat_id).total.cost,
+                ));
+            }
+        }
+        if let Some(limit) = self.per_model.get(model_id) {
+            let spent = ledger
+                .by_model
+                .get(model_id)
+                .map_or(0.0, |usage| usage.cost);
+            limits.push((format!("model {}", model_id), *limit, spent));
+        }
+        if let Some(limit) = self.daily {
+            let spent = ledger.by_day.get(day).map_or(0.0, |usage| usage.cost);
+            limits.push((format!("day {}", day), limit, spent));
+        }
+        if let Some(limit) = self.monthly {
+            // Day keys are YYYY-MM-DD, so the month is the first seven characters
+            let month = &day[..7];
+            let spent = ledger
+                .by_day
+                .iter()
+                .filter(|(key, _)| key.starts_with(month))
+                .map(|(_, usage)| usage.cost)
+                .sum();
+            limits.push((format!("month {}", month), limit, spent));
+        }
+
+        for (budget, limit, spent) in limits {
+            if spent + estimated_cost > limit {
+                return Err(GenerationError::BudgetExceeded {
+                    message: format!(
+                        "Budget for {} would be exceeded: ${:.4} spent of ${:.4}, next request estimated at ${:.4}",
+                        budget, spent, limit, estimated_cost
+                    ),
+                    budget,
+                    limit,
+                    spent,
+                    estimated_cost,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+pub mod budget;
+
+use crate::messages::store::MessageStore;
+use crate::messages::AssistantMessage;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const USAGE_LEDGER_LABEL: &str = "usage_ledger";
+
+/// Accumulated token counts and spend
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct UsageTotals {
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+    pub cost: f64, // USD
+}
+
+impl UsageTotals {
+    fn add(&mut self, other: &UsageTotals) {
+        self.requests += other.requests;
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.total_tokens += other.total_tokens;
+        self.cost += other.cost;
+    }
+}
+
+/// Usage for a single chat, broken down by model and day
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ChatUsage {
+    pub total: UsageTotals,
+    pub by_model: BTreeMap<String, UsageTotals>,
+    pub by_day: BTreeMap<String, UsageTotals>, // Keyed by UTC date, YYYY-MM-DD
+}
+
+/// Running totals of every completed generation, kept in the runtime store
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct UsageLedger {
+    pub total: UsageTotals,
+    pub by_model: BTreeMap<String, UsageTotals>,
+    pub by_day: BTreeMap<String, UsageTotals>,
+    pub by_chat: BTreeMap<String, ChatUsage>,
+}
+
+impl UsageLedger {
+    pub fn load(store: &MessageStore) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(store.read_json(USAGE_LEDGER_LABEL)?.unwrap_or_default())
+    }
+
+    pub fn save(&self, store: &MessageStore) -> Result<(), Box<dyn std::error::Error>> {
+        store.write_json(USAGE_LEDGER_LABEL, self)
+    }
+
+    /// Add one reply's usage under its chat, model and day. `model_id` is the configured ID
+    /// of the model, which budgets are keyed by; providers may report another name.
+    pub fn record(
+        &mut self,
+        chat_id: &str,
+        timestamp_ms: u64,
+        model_id: &str,
+        message: &AssistantMessage,
+    ) {
+        let usage = message_usage(message);
+        let day = day_key(timestamp_ms);
+
+        self.total.add(&usage);
+        self.by_model
+            .entry(model_id.to_string())
+            .or_default()
+            .add(&usage);
+        self.by_day.entry(day.clone()).or_default().add(&usage);
+
+        let chat = self.by_chat.entry(chat_id.to_string()).or_default();
+        chat.total.add(&usage);
+        chat.by_model
+            .entry(model_id.to_string())
+            .or_default()
+            .add(&usage);
+        chat.by_day.entry(day).or_default().add(&usage);
+    }
+
+    pub fn chat(&self, chat_id: &str) -> ChatUsage {
+        self.by_chat.get(chat_id).cloned().unwrap_or_default()
+    }
+}
+
+/// Tokens and cost of a single reply. The provider's reported cost is used when there is
+/// one; otherwise it is worked out from the model's per-million-token prices.
+pub fn message_usage(message: &AssistantMessage) -> UsageTotals {
+    let usage = &message.usage;
+    let cost = usage.cost.unwrap_or_else(|| {
+        let input = message.input_cost_per_million_tokens.unwrap_or(0.0);
+        let output = message.output_cost_per_million_tokens.unwrap_or(0.0);
+        (usage.prompt_tokens as f64 * input + usage.completion_tokens as f64 * output) / 1_000_000.0
+    });
+
+    UsageTotals {
+        requests: 1,
+        prompt_tokens: usage.prompt_tokens as u64,
+        completion_tokens: usage.completion_tokens as u64,
+        total_tokens: usage.total_tokens as u64,
+        cost,
+    }
+}
+
+// UTC calendar date for a millisecond timestamp, from Howard Hinnant's days-to-civil algorithm
+pub fn day_key(timestamp_ms: u64) -> String {
+    let days = (timestamp_ms / 86_400_000) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}